@@ -0,0 +1,30 @@
+use {crate::outbox::Outbox, tokio::sync::mpsc::UnboundedReceiver};
+
+/// problems operators have to notice during the event, e.g. OBS has gone away.
+/// they are always logged, and posted to the alert channel only if it's configured.
+pub(crate) struct Alerts {
+    enabled: bool,
+    outbox: Outbox<String>,
+}
+
+impl Alerts {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            outbox: Outbox::new(),
+        }
+    }
+
+    pub(crate) fn send(&self, text: impl Into<String>) {
+        let text = text.into();
+        tracing::warn!("{}", text);
+
+        if self.enabled {
+            self.outbox.send(text);
+        }
+    }
+
+    pub(crate) fn take_receiver(&self) -> Option<UnboundedReceiver<String>> {
+        self.outbox.take_receiver()
+    }
+}
@@ -0,0 +1,177 @@
+use {
+    crate::{model::Service, moderation::Incoming, outbox::Outbox},
+    parking_lot::Mutex,
+    std::{
+        collections::HashMap,
+        sync::atomic::{AtomicU64, Ordering},
+    },
+    tokio::sync::mpsc::UnboundedReceiver,
+};
+
+/// a message waiting for approval, as shown to operators.
+pub(crate) struct ApprovalRequest {
+    pub(crate) id: u64,
+    pub(crate) summary: String,
+    /// the request for the message before it was edited, which can't be approved anymore
+    pub(crate) supersedes: Option<u64>,
+}
+
+/// messages which passed moderation but are held until an operator approves them.
+/// requests are handed to whoever shows them to operators through `take_requests`.
+pub(crate) struct ApprovalQueue {
+    enabled: bool,
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<u64, Incoming>>,
+    requests: Outbox<ApprovalRequest>,
+}
+
+impl ApprovalQueue {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            next_id: AtomicU64::new(0),
+            pending: Mutex::new(HashMap::new()),
+            requests: Outbox::new(),
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub(crate) fn enqueue(&self, incoming: Incoming) {
+        self.request(incoming, None);
+    }
+
+    fn request(&self, incoming: Incoming, supersedes: Option<u64>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let summary = incoming.describe();
+
+        self.pending.lock().insert(id, incoming);
+        self.requests.send(ApprovalRequest {
+            id,
+            summary,
+            supersedes,
+        });
+    }
+
+    /// can be called only once. returns None afterwards.
+    pub(crate) fn take_requests(&self) -> Option<UnboundedReceiver<ApprovalRequest>> {
+        self.requests.take_receiver()
+    }
+
+    /// removes the message from the queue. None if it's already approved or rejected.
+    pub(crate) fn take(&self, id: u64) -> Option<Incoming> {
+        self.pending.lock().remove(&id)
+    }
+
+    pub(crate) fn contains_source(&self, service: Service, source_id: &str) -> bool {
+        self.pending
+            .lock()
+            .values()
+            .any(|x| is_from(x, service, source_id))
+    }
+
+    /// drops the pending message, e.g. deleted by the author. false if there's none.
+    pub(crate) fn remove_by_source(&self, service: Service, source_id: &str) -> bool {
+        self.take_by_source(service, source_id).is_some()
+    }
+
+    /// the ID of the pending message removed
+    fn take_by_source(&self, service: Service, source_id: &str) -> Option<u64> {
+        let mut pending = self.pending.lock();
+
+        let id = *pending
+            .iter()
+            .find(|(_, x)| is_from(x, service, source_id))?
+            .0;
+
+        pending.remove(&id);
+        Some(id)
+    }
+
+    /// asks for approval of the edited message instead of the old one, so that operators see
+    /// the new text. false if the old one is no longer pending, and the edited one is dropped.
+    pub(crate) fn replace_by_source(&self, incoming: Incoming) -> bool {
+        let Some(source_id) = &incoming.source_id else {
+            return false;
+        };
+
+        let Some(old) = self.take_by_source(incoming.service, source_id) else {
+            return false;
+        };
+
+        self.request(incoming, Some(old));
+        true
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.pending.lock().len()
+    }
+}
+
+fn is_from(incoming: &Incoming, service: Service, source_id: &str) -> bool {
+    incoming.service == service && incoming.source_id.as_deref() == Some(source_id)
+}
+
+#[test]
+fn test_approval_queue() {
+    use crate::test_util::incoming;
+
+    let queue = ApprovalQueue::new(true);
+    let mut requests = queue.take_requests().unwrap();
+    assert!(queue.take_requests().is_none());
+
+    for text in ["first", "second"] {
+        queue.enqueue(incoming(Service::Youtube, "viewer", text));
+    }
+
+    let first = requests.try_recv().unwrap();
+    let second = requests.try_recv().unwrap();
+    assert_eq!(first.summary, "[youtube] viewer: first");
+    assert_eq!(queue.len(), 2);
+
+    assert_eq!(
+        queue.take(second.id).unwrap().content.plain_text(),
+        "second"
+    );
+    assert!(queue.take(second.id).is_none());
+    assert_eq!(queue.len(), 1);
+}
+
+#[test]
+fn test_approval_queue_by_source() {
+    let queue = ApprovalQueue::new(true);
+    let mut requests = queue.take_requests().unwrap();
+
+    let incoming = |source_id: &str, text: &str| Incoming {
+        source_id: Some(source_id.into()),
+        ..crate::test_util::incoming(Service::Discord, "viewer", text)
+    };
+
+    queue.enqueue(incoming("1", "hello"));
+    queue.enqueue(incoming("2", "bad word"));
+    let first = requests.try_recv().unwrap();
+    requests.try_recv().unwrap();
+
+    // edited before approved
+    assert!(queue.replace_by_source(incoming("1", "hello, edited")));
+    assert!(queue.take(first.id).is_none());
+    let edited = requests.try_recv().unwrap();
+    assert_eq!(edited.summary, "[discord] viewer: hello, edited");
+    assert_eq!(edited.supersedes, Some(first.id));
+    assert!(!queue.replace_by_source(incoming("3", "never pending")));
+
+    // deleted before approved
+    assert!(queue.contains_source(Service::Discord, "2"));
+    assert!(queue.remove_by_source(Service::Discord, "2"));
+    assert!(!queue.contains_source(Service::Discord, "2"));
+    assert!(!queue.remove_by_source(Service::Discord, "2"));
+    assert!(!queue.contains_source(Service::Youtube, "1"));
+
+    assert_eq!(queue.len(), 1);
+    assert_eq!(
+        queue.take(edited.id).unwrap().content.plain_text(),
+        "hello, edited"
+    );
+}
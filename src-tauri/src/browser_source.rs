@@ -0,0 +1,91 @@
+use {
+    crate::Context,
+    anyhow::{Context as _, Result},
+    axum::{
+        extract::{
+            ws::{Message, WebSocket, WebSocketUpgrade},
+            State,
+        },
+        response::Response,
+        routing::get,
+        Router,
+    },
+    std::{net::SocketAddr, path::PathBuf, sync::Arc},
+    tokio::sync::broadcast::error::RecvError,
+    tower_http::services::{ServeDir, ServeFile},
+};
+
+/// serves the built frontend over HTTP so that OBS can load the screen as a browser source.
+/// the same events as the tauri window receives are pushed through `/events` WebSocket.
+pub(crate) struct BrowserSource {
+    ctx: Arc<Context>,
+    dist_dir: PathBuf,
+}
+
+impl BrowserSource {
+    pub(crate) fn new(ctx: Arc<Context>, dist_dir: PathBuf) -> Self {
+        Self { ctx, dist_dir }
+    }
+
+    pub(crate) async fn start(self, addr: SocketAddr) -> Result<()> {
+        let index = ServeFile::new(self.dist_dir.join("index.html"));
+
+        let app = Router::new()
+            .route("/events", get(events))
+            .fallback_service(ServeDir::new(&self.dist_dir).fallback(index))
+            .with_state(self.ctx);
+
+        tracing::info!(
+            "browser source is available at http://{}/ (serving {})",
+            addr,
+            self.dist_dir.display()
+        );
+
+        axum::Server::try_bind(&addr)
+            .context("failed to bind browser source server")?
+            .serve(app.into_make_service())
+            .await
+            .context("error occur while running browser source server")
+    }
+}
+
+async fn events(State(ctx): State<Arc<Context>>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(move |socket| forward(ctx, socket))
+}
+
+async fn forward(ctx: Arc<Context>, mut socket: WebSocket) {
+    // subscribe while holding the state so that no event is lost or duplicated
+    // between the snapshot and the following events.
+    let (mut rx, snapshot) = {
+        let state = ctx.screen_state.read().await;
+        (ctx.screen_events.subscribe(), state.snapshot(&ctx).await)
+    };
+
+    if socket.send(Message::Text(snapshot)).await.is_err() {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            event = rx.recv() => match event {
+                Ok(json) => {
+                    if socket.send(Message::Text(json)).await.is_err() {
+                        break;
+                    }
+                }
+
+                Err(RecvError::Lagged(n)) => {
+                    tracing::warn!("browser source skipped {} events", n);
+                }
+
+                Err(RecvError::Closed) => break,
+            },
+
+            // we don't expect any messages from views. just watching for disconnection.
+            message = socket.recv() => match message {
+                Some(Ok(_)) => {}
+                _ => break,
+            },
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use {
+    crate::{
+        control::{self, ControlCommand, PresentationCommand, TimerCommand},
+        Context,
+    },
+    anyhow::{Context as _, Result},
+    axum::{
+        extract::{
+            ws::{Message, WebSocket, WebSocketUpgrade},
+            Path, Query, State,
+        },
+        response::Response,
+        routing::{delete, get, post, put},
+        Json, Router,
+    },
+    serde::Deserialize,
+    std::{net::SocketAddr, sync::Arc},
+};
+
+type Ctx = State<Arc<Context>>;
+
+#[derive(Deserialize)]
+struct Simulation {
+    #[serde(default)]
+    simulation: bool,
+}
+
+/// local HTTP/WebSocket server to control the screen without Discord.
+///
+/// every endpoint replies with the same text as the Discord bot does.
+/// `POST /command` and `/ws` accept a `ControlCommand` in JSON,
+/// e.g. `{"command": "presentation", "action": "pop"}`.
+pub(crate) struct ControlServer {
+    ctx: Arc<Context>,
+}
+
+impl ControlServer {
+    pub(crate) fn new(ctx: Arc<Context>) -> Self {
+        Self { ctx }
+    }
+
+    pub(crate) async fn start(self, addr: SocketAddr) -> Result<()> {
+        use ControlCommand::*;
+        use PresentationCommand::*;
+
+        let app = Router::new()
+            .route("/command", post(command))
+            .route("/ws", get(websocket))
+            .route("/pause", post(|ctx: Ctx| run(ctx, Pause)))
+            .route("/resume", post(|ctx: Ctx| run(ctx, Resume)))
+            .route(
+                "/undo",
+                post(|State(ctx): Ctx| async move { control::undo(&ctx).await }),
+            )
+            .route(
+                "/notification",
+                put(|ctx: Ctx, text: String| run(ctx, SetNotification { text })),
+            )
+            .route("/timeline", get(|ctx: Ctx| run(ctx, TimelineList)))
+            .route("/timeline/clear", post(|ctx: Ctx| run(ctx, TimelineClear)))
+            .route(
+                "/timeline/:id",
+                delete(|ctx: Ctx, Path(id): Path<u64>| run(ctx, TimelineRemove { id })),
+            )
+            .route(
+                "/youtube",
+                put(|ctx: Ctx, target: String| run(ctx, Youtube { target })),
+            )
+            .route(
+                "/presentations",
+                get(|ctx: Ctx| run(ctx, Presentation(List))).post(
+                    |ctx: Ctx, Json(p): Json<crate::presentations::Presentation>| {
+                        run(ctx, Presentation(Push(p)))
+                    },
+                ),
+            )
+            .route(
+                "/presentations/pop",
+                post(|ctx: Ctx| run(ctx, Presentation(Pop))),
+            )
+            .route(
+                "/presentations/reorder",
+                post(|ctx: Ctx, Json(map): Json<Vec<usize>>| {
+                    run(ctx, Presentation(Reorder { map }))
+                }),
+            )
+            .route(
+                "/presentations/:index",
+                delete(|ctx: Ctx, Path(index): Path<usize>| {
+                    run(ctx, Presentation(Remove { index }))
+                }),
+            )
+            .route(
+                "/presentations/:index/title",
+                put(|ctx: Ctx, Path(index): Path<usize>, new_title: String| {
+                    run(ctx, Presentation(Update { index, new_title }))
+                }),
+            )
+            .route(
+                "/timer/pause",
+                post(|ctx: Ctx| run(ctx, Timer(TimerCommand::Pause))),
+            )
+            .route(
+                "/timer/resume",
+                post(|ctx: Ctx| run(ctx, Timer(TimerCommand::Resume))),
+            )
+            .route(
+                "/timer/reset",
+                post(|ctx: Ctx| run(ctx, Timer(TimerCommand::Reset))),
+            )
+            .route(
+                "/timer/stop",
+                post(|ctx: Ctx| run(ctx, Timer(TimerCommand::Stop))),
+            )
+            .route(
+                "/timer/extend/:secs",
+                post(|ctx: Ctx, Path(secs): Path<u64>| {
+                    run(ctx, Timer(TimerCommand::Extend { secs }))
+                }),
+            )
+            .route(
+                "/tweet",
+                post(|ctx: Ctx, Json(cmd): Json<ControlCommand>| async move {
+                    match cmd {
+                        cmd @ Tweet { .. } => run(ctx, cmd).await,
+                        _ => "body must be a tweet command".into(),
+                    }
+                }),
+            )
+            .route(
+                "/presentation_tweet",
+                post(|ctx: Ctx, Query(q): Query<Simulation>| {
+                    run(
+                        ctx,
+                        PresentationTweet {
+                            simulation: q.simulation,
+                        },
+                    )
+                }),
+            )
+            .with_state(self.ctx);
+
+        tracing::info!("control server is listening at {}", addr);
+
+        axum::Server::try_bind(&addr)
+            .context("failed to bind control server")?
+            .serve(app.into_make_service())
+            .await
+            .context("error occur while running control server")
+    }
+}
+
+async fn run(State(ctx): Ctx, cmd: ControlCommand) -> String {
+    control::execute(&ctx, cmd).await
+}
+
+async fn command(ctx: Ctx, Json(cmd): Json<ControlCommand>) -> String {
+    run(ctx, cmd).await
+}
+
+async fn websocket(State(ctx): Ctx, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(move |socket| handle_socket(ctx, socket))
+}
+
+async fn handle_socket(ctx: Arc<Context>, mut socket: WebSocket) {
+    while let Some(Ok(message)) = socket.recv().await {
+        let Message::Text(text) = message else {
+            continue;
+        };
+
+        let reply = match serde_json::from_str::<ControlCommand>(&text) {
+            Ok(cmd) => control::execute(&ctx, cmd).await,
+            Err(e) => format!("invalid command: {}", e),
+        };
+
+        if socket.send(Message::Text(reply)).await.is_err() {
+            break;
+        }
+    }
+}
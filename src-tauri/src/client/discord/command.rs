@@ -0,0 +1,869 @@
+use {
+    super::{Command, PREFIX},
+    crate::{
+        control::{ControlCommand, ObsCommand, PresentationCommand, TimerCommand},
+        model::Service,
+        moderation,
+    },
+    once_cell::sync::Lazy,
+    regex::Regex,
+    std::collections::HashMap,
+};
+
+const FOOTER_FLAGS_HELP: &str =
+    "unknown footer flag. supported flags are d: Discord, y: Youtube, t: Twitter";
+
+fn extract_user_id_from_mention(mention_text: &str) -> Option<u64> {
+    static MENTION_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<@!?(?P<id>\d+)>").unwrap());
+
+    let id_str = MENTION_REGEX.captures(mention_text)?.name("id")?.as_str();
+
+    id_str.parse().ok()
+}
+
+#[test]
+fn test_extract_user_id() {
+    assert_eq!(extract_user_id_from_mention("<@!123>"), Some(123));
+    assert_eq!(extract_user_id_from_mention("<@!012345>"), Some(12345));
+    assert_eq!(extract_user_id_from_mention("<@123>"), Some(123));
+    assert_eq!(extract_user_id_from_mention("hogehoge"), None);
+}
+
+/// footer flags of tweet command like "dy" into (youtube, discord, twitter)
+fn parse_footer_flags(flags: &str) -> Option<(bool, bool, bool)> {
+    let mut footers = (false, false, false);
+
+    for c in flags.chars() {
+        match c.to_ascii_lowercase() {
+            'y' => footers.0 = true,
+            'd' => footers.1 = true,
+            't' => footers.2 = true,
+            _ => return None,
+        }
+    }
+
+    Some(footers)
+}
+
+/// "90", "90s", "10m" or "1h" in seconds
+fn parse_duration(text: &str) -> Option<u64> {
+    let (number, unit) = match text.char_indices().last()? {
+        (i, 's') => (&text[..i], 1),
+        (i, 'm') => (&text[..i], 60),
+        (i, 'h') => (&text[..i], 60 * 60),
+        _ => (text, 1),
+    };
+
+    number.parse::<u64>().ok()?.checked_mul(unit)
+}
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("90"), Some(90));
+    assert_eq!(parse_duration("90s"), Some(90));
+    assert_eq!(parse_duration("10m"), Some(600));
+    assert_eq!(parse_duration("1h"), Some(3600));
+    assert_eq!(parse_duration("m"), None);
+    assert_eq!(parse_duration("name"), None);
+}
+
+/// message ID from itself or its link
+fn message_id_of(text: &str) -> Option<u64> {
+    text.trim().rsplit('/').next()?.parse().ok()
+}
+
+/// "<mention>" or a youtube name into the form of `moderation::author_key`
+fn mute_target(words: &[Word], name: &str) -> String {
+    if let [mention] = words {
+        if let Some(id) = extract_user_id_from_mention(&mention.text) {
+            return moderation::author_key(Service::Discord, &id.to_string());
+        }
+    }
+
+    moderation::author_key(Service::Youtube, name)
+}
+
+/// a token of a command and where it is written in the message.
+struct Word {
+    text: String,
+    /// byte range in the tokenized text, including the quotes
+    start: usize,
+    end: usize,
+}
+
+/// splits the text at whitespaces like a shell does.
+/// "double quoted" (with \" and \\ escapes), 'single quoted', `inline code` and ```code blocks```
+/// at the beginning of a word are kept as one argument without the quotes.
+fn tokenize(text: &str) -> Result<Vec<Word>, &'static str> {
+    let mut tokens = vec![];
+    let mut rest = text.trim_start();
+
+    while !rest.is_empty() {
+        let (token, remaining) = next_token(rest)?;
+        tokens.push(Word {
+            text: token,
+            start: text.len() - rest.len(),
+            end: text.len() - remaining.len(),
+        });
+        rest = remaining.trim_start();
+    }
+
+    Ok(tokens)
+}
+
+fn next_token(text: &str) -> Result<(String, &str), &'static str> {
+    if let Some(body) = text.strip_prefix("```") {
+        let end = body.find("```").ok_or("code block is not closed")?;
+        return Ok((body[..end].trim().to_string(), &body[end + 3..]));
+    }
+
+    match text.chars().next() {
+        Some('"') => {
+            let mut token = String::new();
+            let mut chars = text.char_indices().skip(1);
+
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '"' => return Ok((token, &text[i + 1..])),
+                    '\\' => match chars.next() {
+                        Some((_, c @ ('"' | '\\'))) => token.push(c),
+                        Some((_, c)) => {
+                            token.push('\\');
+                            token.push(c);
+                        }
+                        None => break,
+                    },
+                    c => token.push(c),
+                }
+            }
+
+            Err("quote is not closed")
+        }
+
+        Some(quote @ ('\'' | '`')) => {
+            let body = &text[1..];
+            let end = body.find(quote).ok_or("quote is not closed")?;
+            Ok((body[..end].to_string(), &body[end + 1..]))
+        }
+
+        _ => {
+            let end = text.find(char::is_whitespace).unwrap_or(text.len());
+            Ok((text[..end].to_string(), &text[end..]))
+        }
+    }
+}
+
+#[cfg(test)]
+fn tokenize_texts(text: &str) -> Result<Vec<String>, &'static str> {
+    Ok(tokenize(text)?.into_iter().map(|x| x.text).collect())
+}
+
+#[test]
+fn test_tokenize() {
+    assert_eq!(
+        tokenize_texts("  presentations  update 0 限界 LT ").unwrap(),
+        ["presentations", "update", "0", "限界", "LT"]
+    );
+    assert_eq!(
+        tokenize_texts(r#"update 0 "two  spaces \"quoted\"" 'it''s'"#).unwrap(),
+        ["update", "0", "two  spaces \"quoted\"", "it", "s"]
+    );
+    assert_eq!(
+        tokenize_texts("tweet -dy ```\nhello\n  world\n```").unwrap(),
+        ["tweet", "-dy", "hello\n  world"]
+    );
+    assert_eq!(tokenize_texts("don't").unwrap(), ["don't"]);
+    assert_eq!(tokenize_texts("\"open").unwrap_err(), "quote is not closed");
+    assert_eq!(
+        tokenize_texts("```open").unwrap_err(),
+        "code block is not closed"
+    );
+}
+
+#[derive(Clone, Copy)]
+pub(super) enum Kind {
+    /// user ID from "<@id>"
+    Mention,
+    /// user mention or youtube name into the author key. takes the rest of words as written.
+    Target,
+    Integer,
+    /// integers separated by spaces. takes the rest of words.
+    Indices,
+    /// secs from "90s", "10m" or "1h"
+    Duration,
+    /// message ID from itself or its link
+    Message,
+    /// a word or a quoted text
+    Word,
+    /// takes the rest of words as written, keeping line breaks and spaces between them.
+    Text,
+    /// `Text` to be posted outside Discord, where mentions would be left as "<@id>".
+    /// they are taken only in a code block, as the body is written.
+    Body,
+    /// footer flags of tweet like "-dy". skipped unless the word starts with '-'.
+    Flags,
+}
+
+enum Value {
+    Number(u64),
+    Numbers(Vec<usize>),
+    Text(String),
+    Flags((bool, bool, bool)),
+}
+
+impl Kind {
+    fn is_greedy(self) -> bool {
+        matches!(self, Kind::Target | Kind::Indices | Kind::Text | Kind::Body)
+    }
+
+    fn accepts(self, word: &str) -> bool {
+        !matches!(self, Kind::Flags) || word.starts_with('-')
+    }
+
+    /// `raw` is the part of the message which `words` are tokenized from.
+    fn parse(self, words: &[Word], raw: &str) -> Result<Value, &'static str> {
+        let first = &words[0].text;
+        // a single word is taken without its quotes
+        let text = match words {
+            [word] => &word.text,
+            _ => raw,
+        };
+
+        match self {
+            Kind::Mention => extract_user_id_from_mention(first)
+                .map(Value::Number)
+                .ok_or("must be a user mention"),
+
+            Kind::Target => Ok(Value::Text(mute_target(words, text))),
+
+            Kind::Integer => first
+                .parse()
+                .map(Value::Number)
+                .map_err(|_| "must be a non-negative integer"),
+
+            Kind::Indices => words
+                .iter()
+                .map(|x| x.text.parse())
+                .collect::<Result<_, _>>()
+                .map(Value::Numbers)
+                .map_err(|_| "must be non-negative integers"),
+
+            Kind::Duration => parse_duration(first)
+                .map(Value::Number)
+                .ok_or("must be like 90s, 10m or 1h"),
+
+            Kind::Message => message_id_of(first)
+                .map(Value::Number)
+                .ok_or("must be a message ID or link"),
+
+            Kind::Word => Ok(Value::Text(first.clone())),
+
+            Kind::Text => Ok(Value::Text(text.to_string())),
+
+            Kind::Body => {
+                static MENTION_REGEX: Lazy<Regex> =
+                    Lazy::new(|| Regex::new(r"<(@[!&]?|#)\d+>").unwrap());
+
+                let code_block = words.len() == 1 && raw.starts_with("```");
+
+                if !code_block && MENTION_REGEX.is_match(text) {
+                    return Err("mentions can't be posted as is. wrap the body with ```");
+                }
+
+                Ok(Value::Text(text.to_string()))
+            }
+
+            Kind::Flags => parse_footer_flags(first.trim_start_matches('-'))
+                .map(Value::Flags)
+                .ok_or(FOOTER_FLAGS_HELP),
+        }
+    }
+}
+
+pub(super) struct Arg {
+    pub(super) name: &'static str,
+    pub(super) description: &'static str,
+    pub(super) kind: Kind,
+    pub(super) required: bool,
+}
+
+const fn arg(name: &'static str, description: &'static str, kind: Kind) -> Arg {
+    Arg {
+        name,
+        description,
+        kind,
+        required: true,
+    }
+}
+
+const fn opt(name: &'static str, description: &'static str, kind: Kind) -> Arg {
+    Arg {
+        name,
+        description,
+        kind,
+        required: false,
+    }
+}
+
+/// values bound to the arguments of a command, taken in the declared order.
+struct Values(std::vec::IntoIter<Option<Value>>);
+
+impl Values {
+    fn take(&mut self) -> Option<Value> {
+        self.0.next().flatten()
+    }
+
+    fn number(&mut self) -> Option<u64> {
+        match self.take()? {
+            Value::Number(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    fn numbers(&mut self) -> Option<Vec<usize>> {
+        match self.take()? {
+            Value::Numbers(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    fn text(&mut self) -> Option<String> {
+        match self.take()? {
+            Value::Text(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    fn flags(&mut self) -> Option<(bool, bool, bool)> {
+        match self.take()? {
+            Value::Flags(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+/// a command for both `g!live` and `/live`
+pub(super) struct Spec {
+    /// subcommand words after the prefix, e.g. "presentations update"
+    pub(super) name: &'static str,
+    pub(super) description: &'static str,
+    pub(super) args: &'static [Arg],
+    /// None only if the values don't match `args`, which never happens.
+    build: fn(&mut Values) -> Option<Command>,
+}
+
+fn tweet(values: &mut Values, simulation: bool) -> Option<Command> {
+    let (with_youtube_footer, with_discord_footer, with_twitter_footer) =
+        values.flags().unwrap_or_default();
+
+    Some(Command::Control(ControlCommand::Tweet {
+        with_youtube_footer,
+        with_discord_footer,
+        with_twitter_footer,
+        msg: values.text()?,
+        simulation,
+    }))
+}
+
+const CONFIRM_ARGS: &[Arg] = &[opt(
+    "confirm",
+    "`confirm` to skip the confirmation",
+    Kind::Word,
+)];
+
+fn confirmed(values: &mut Values) -> bool {
+    values.text().as_deref() == Some("confirm")
+}
+
+const TWEET_ARGS: &[Arg] = &[
+    opt(
+        "footers",
+        "like -dy. any of d: Discord, y: Youtube, t: Twitter",
+        Kind::Flags,
+    ),
+    arg(
+        "message",
+        "body. wrap it with ``` to post mentions as written",
+        Kind::Body,
+    ),
+];
+
+#[rustfmt::skip]
+pub(super) const COMMANDS: &[Spec] = {
+    use {
+        Command::*,
+        ControlCommand::*,
+        Kind::*,
+        PresentationCommand::{List, Pop, Remove, Reorder, Update},
+    };
+
+    &[
+        Spec { name: "pause", description: "switch to the waiting screen", args: &[], build: |_| Some(Control(Pause)) },
+        Spec { name: "resume", description: "switch to the LT screen", args: &[], build: |_| Some(Control(Resume)) },
+        Spec { name: "status", description: "show the screen, the queue and the OBS connection", args: &[], build: |_| Some(Control(Status)) },
+        Spec { name: "undo", description: "revert the last pause, resume, pop, set_notification, mute_user or timer pause/resume", args: &[], build: |_| Some(Undo) },
+        Spec { name: "listen", description: "show messages in this channel on the timeline", args: &[], build: |_| Some(Listen) },
+        Spec { name: "stop_listening", description: "stop showing discord messages", args: &[], build: |_| Some(StopListening) },
+        Spec { name: "clear_timeline", description: "remove everything on the timeline", args: &[], build: |_| Some(Control(TimelineClear)) },
+        Spec {
+            name: "delete",
+            description: "remove a discord message from the timeline. reply to the message or give its ID",
+            args: &[opt("message", "message ID or link", Message)],
+            build: |v| Some(Delete { message_id: v.number() }),
+        },
+        Spec { name: "timeline", description: "show the latest items on the timeline with their IDs", args: &[], build: |_| Some(Control(TimelineList)) },
+        Spec {
+            name: "remove",
+            description: "remove an item from the timeline, e.g. a youtube comment",
+            args: &[arg("id", "ID shown by `timeline`", Integer)],
+            build: |v| Some(Control(TimelineRemove { id: v.number()? })),
+        },
+        Spec {
+            name: "mute_user",
+            description: "hide messages from a user and remove the ones shown",
+            args: &[
+                arg("user", "user mention or youtube name", Target),
+                opt("duration", "e.g. 90s, 10m or 1h. until unmuted if omitted", Duration),
+            ],
+            build: |v| Some(Control(MuteUser { user: v.text()?, duration_secs: v.number() })),
+        },
+        Spec {
+            name: "unmute_user",
+            description: "show messages from a muted user again",
+            args: &[arg("user", "user mention or youtube name", Target)],
+            build: |v| Some(Control(UnmuteUser { user: v.text()? })),
+        },
+        Spec {
+            name: "youtube",
+            description: "switch the youtube live chat to show",
+            args: &[arg("target", "video ID, URL, @handle or channel ID", Word)],
+            build: |v| Some(Control(Youtube { target: v.text()? })),
+        },
+        Spec {
+            name: "set_notification",
+            description: "set the notification text",
+            args: &[arg("text", "notification", Text)],
+            build: |v| Some(Control(SetNotification { text: v.text()? })),
+        },
+        Spec { name: "presentations pop", description: "start the next presentation", args: &[], build: |_| Some(Control(Presentation(Pop))) },
+        Spec { name: "presentations list", description: "show the queue", args: &[], build: |_| Some(Control(Presentation(List))) },
+        Spec {
+            name: "presentations push",
+            description: "add a presentation to the queue",
+            args: &[arg("user", "presenter mention", Mention), arg("title", "title", Text)],
+            build: |v| Some(PresentationPush { user_id: v.number()?, title: v.text()? }),
+        },
+        Spec {
+            name: "presentations remove",
+            description: "remove a presentation from the queue",
+            args: &[arg("index", "index in the list", Integer)],
+            build: |v| Some(Control(Presentation(Remove { index: v.number()? as usize }))),
+        },
+        Spec {
+            name: "presentations update",
+            description: "change the title of a presentation in the queue",
+            args: &[arg("index", "index in the list", Integer), arg("title", "new title", Text)],
+            build: |v| Some(Control(Presentation(Update { index: v.number()? as usize, new_title: v.text()? }))),
+        },
+        Spec {
+            name: "presentations reorder",
+            description: "move presentations to the front in the order",
+            args: &[arg("indices", "indices in the list", Indices)],
+            build: |v| Some(Control(Presentation(Reorder { map: v.numbers()? }))),
+        },
+        Spec { name: "timer pause", description: "pause the timer", args: &[], build: |_| Some(Control(Timer(TimerCommand::Pause))) },
+        Spec { name: "timer resume", description: "resume the timer", args: &[], build: |_| Some(Control(Timer(TimerCommand::Resume))) },
+        Spec { name: "timer reset", description: "restart the countdown", args: &[], build: |_| Some(Control(Timer(TimerCommand::Reset))) },
+        Spec { name: "timer stop", description: "hide the timer", args: &[], build: |_| Some(Control(Timer(TimerCommand::Stop))) },
+        Spec {
+            name: "timer extend",
+            description: "give more time",
+            args: &[arg("secs", "seconds to add", Integer)],
+            build: |v| Some(Control(Timer(TimerCommand::Extend { secs: v.number()? }))),
+        },
+        Spec { name: "stream start", description: "start streaming in OBS", args: &[], build: |_| Some(Control(Obs(ObsCommand::StreamStart))) },
+        Spec { name: "stream stop", description: "stop streaming in OBS", args: CONFIRM_ARGS, build: |v| Some(Control(Obs(ObsCommand::StreamStop { confirmed: confirmed(v) }))) },
+        Spec { name: "record start", description: "start recording in OBS", args: &[], build: |_| Some(Control(Obs(ObsCommand::RecordStart))) },
+        Spec { name: "record stop", description: "stop recording in OBS", args: CONFIRM_ARGS, build: |v| Some(Control(Obs(ObsCommand::RecordStop { confirmed: confirmed(v) }))) },
+        Spec { name: "record pause", description: "pause recording in OBS", args: &[], build: |_| Some(Control(Obs(ObsCommand::RecordPause))) },
+        Spec { name: "record resume", description: "resume recording in OBS", args: &[], build: |_| Some(Control(Obs(ObsCommand::RecordResume))) },
+        Spec { name: "replay save", description: "save the OBS replay buffer", args: &[], build: |_| Some(Control(Obs(ObsCommand::ReplaySave))) },
+        Spec {
+            name: "screenshot",
+            description: "save a screenshot of an OBS scene",
+            args: &[arg("scene", "scene name", Text)],
+            build: |v| Some(Control(Obs(ObsCommand::Screenshot { scene: v.text()? }))),
+        },
+        Spec { name: "tweet", description: "post to every enabled SNS", args: TWEET_ARGS, build: |v| tweet(v, false) },
+        Spec { name: "tweet_simulation", description: "show what tweet would post", args: TWEET_ARGS, build: |v| tweet(v, true) },
+        Spec { name: "presentation_tweet", description: "post about the current presentation", args: &[], build: |_| Some(Control(PresentationTweet { simulation: false })) },
+        Spec { name: "presentation_tweet_simulation", description: "show what presentation_tweet would post", args: &[], build: |_| Some(Control(PresentationTweet { simulation: true })) },
+        Spec {
+            name: "help",
+            description: "show how to use commands",
+            args: &[opt("command", "e.g. presentations update", Text)],
+            build: |v| Some(Help(v.text())),
+        },
+    ]
+};
+
+/// names of every command, as in `help`
+pub(super) fn names() -> impl Iterator<Item = &'static str> {
+    COMMANDS.iter().map(|x| x.name)
+}
+
+impl Command {
+    /// the name in `COMMANDS` which gives this command
+    pub(super) fn name(&self) -> &'static str {
+        use {Command::*, ControlCommand::*, PresentationCommand::*};
+
+        match self {
+            Help(_) | Invalid(_) => "help",
+            Listen => "listen",
+            StopListening => "stop_listening",
+            Delete { .. } => "delete",
+            Control(TimelineList) => "timeline",
+            Control(TimelineRemove { .. }) => "remove",
+            PresentationPush { .. } | Control(Presentation(Push(_))) => "presentations push",
+            Undo => "undo",
+            Control(SetNotification { .. }) => "set_notification",
+            Control(TimelineClear) => "clear_timeline",
+            Control(MuteUser { .. }) => "mute_user",
+            Control(UnmuteUser { .. }) => "unmute_user",
+            Control(Youtube { .. }) => "youtube",
+            Control(Pause) => "pause",
+            Control(Resume) => "resume",
+            Control(Status) => "status",
+            Control(Presentation(Reorder { .. })) => "presentations reorder",
+            Control(Presentation(Remove { .. })) => "presentations remove",
+            Control(Presentation(Update { .. })) => "presentations update",
+            Control(Presentation(List)) => "presentations list",
+            Control(Presentation(Pop)) => "presentations pop",
+            // only done by undo
+            Control(Presentation(Unpop { .. })) => "undo",
+            Control(Timer(TimerCommand::Pause)) => "timer pause",
+            Control(Timer(TimerCommand::Resume)) => "timer resume",
+            Control(Timer(TimerCommand::Extend { .. })) => "timer extend",
+            Control(Timer(TimerCommand::Reset)) => "timer reset",
+            Control(Timer(TimerCommand::Stop)) => "timer stop",
+            Control(Obs(ObsCommand::StreamStart)) => "stream start",
+            Control(Obs(ObsCommand::StreamStop { .. })) => "stream stop",
+            Control(Obs(ObsCommand::RecordStart)) => "record start",
+            Control(Obs(ObsCommand::RecordStop { .. })) => "record stop",
+            Control(Obs(ObsCommand::RecordPause)) => "record pause",
+            Control(Obs(ObsCommand::RecordResume)) => "record resume",
+            Control(Obs(ObsCommand::ReplaySave)) => "replay save",
+            Control(Obs(ObsCommand::Screenshot { .. })) => "screenshot",
+            Control(PresentationTweet { simulation: false }) => "presentation_tweet",
+            Control(PresentationTweet { simulation: true }) => "presentation_tweet_simulation",
+            Control(Tweet {
+                simulation: false, ..
+            }) => "tweet",
+            Control(Tweet {
+                simulation: true, ..
+            }) => "tweet_simulation",
+        }
+    }
+}
+
+/// the spec whose name matches the most leading words, and the number of the words.
+fn find(words: &[Word]) -> Option<(&'static Spec, usize)> {
+    COMMANDS
+        .iter()
+        .filter_map(|spec| {
+            let len = spec.name.split(' ').count();
+            let matches = words.len() >= len
+                && spec
+                    .name
+                    .split(' ')
+                    .eq(words[..len].iter().map(|x| x.text.as_str()));
+            matches.then_some((spec, len))
+        })
+        .max_by_key(|(_, len)| *len)
+}
+
+fn usage(spec: &Spec) -> String {
+    let mut usage = format!("`{} {}", PREFIX, spec.name);
+
+    for arg in spec.args {
+        let name = if arg.kind.is_greedy() {
+            format!("{}...", arg.name)
+        } else {
+            arg.name.to_string()
+        };
+
+        if arg.required {
+            usage += &format!(" <{}>", name);
+        } else {
+            usage += &format!(" [{}]", name);
+        }
+    }
+
+    usage + "`"
+}
+
+/// the part of `source` from the first word to the last one.
+fn raw<'a>(source: &'a str, words: &[Word]) -> &'a str {
+    match (words.first(), words.last()) {
+        (Some(first), Some(last)) => &source[first.start..last.end],
+        _ => "",
+    }
+}
+
+/// binds words to the arguments. greedy arguments leave words to the following ones.
+/// `source` is the text the words are tokenized from.
+fn bind(args: &[Arg], source: &str, mut words: &[Word]) -> Result<Vec<Option<Value>>, String> {
+    let mut values = vec![];
+
+    for (i, arg) in args.iter().enumerate() {
+        let taken = if arg.kind.is_greedy() {
+            let mut end = words.len();
+
+            for next in args[i + 1..].iter().rev() {
+                if end > 1 {
+                    let last = &words[end - 1..end];
+
+                    if next.required || next.kind.parse(last, raw(source, last)).is_ok() {
+                        end -= 1;
+                    }
+                }
+            }
+
+            end
+        } else {
+            usize::from(words.first().is_some_and(|x| arg.kind.accepts(&x.text)))
+        };
+
+        let (head, tail) = words.split_at(taken);
+        words = tail;
+
+        if head.is_empty() {
+            if arg.required {
+                return Err(format!("missing <{}>", arg.name));
+            }
+
+            values.push(None);
+            continue;
+        }
+
+        let value = arg
+            .kind
+            .parse(head, raw(source, head))
+            .map_err(|e| format!("<{}> {}", arg.name, e))?;
+
+        values.push(Some(value));
+    }
+
+    match words.first() {
+        Some(word) => Err(format!("unexpected argument `{}`", word.text)),
+        None => Ok(values),
+    }
+}
+
+/// parses a text command. None if the message is not a command.
+pub(super) fn parse(msg: &str) -> Option<Command> {
+    let rest = msg.strip_prefix(PREFIX)?;
+
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let words = match tokenize(rest) {
+        Ok(words) if words.is_empty() => return Some(Command::Help(None)),
+        Ok(words) => words,
+        Err(e) => return Some(Command::Invalid(e.to_string())),
+    };
+
+    let Some((spec, len)) = find(&words) else {
+        return Some(Command::Invalid(format!(
+            "unknown command `{}`\n{}",
+            rest.trim(),
+            help(Some(&words[0].text))
+        )));
+    };
+
+    let values = match bind(spec.args, rest, &words[len..]) {
+        Ok(values) => values,
+        Err(e) => return Some(Command::Invalid(format!("{}\nusage: {}", e, usage(spec)))),
+    };
+
+    Some(
+        (spec.build)(&mut Values(values.into_iter()))
+            .unwrap_or_else(|| Command::Invalid(format!("usage: {}", usage(spec)))),
+    )
+}
+
+/// builds the command from the arguments given by name, such as the options of `/live`.
+/// each of them is written as in a text command, e.g. "<@123>" for a user.
+pub(super) fn build(name: &str, mut args: HashMap<&str, String>) -> Command {
+    let Some(spec) = COMMANDS.iter().find(|x| x.name == name) else {
+        return Command::Invalid(format!("unknown command `{}`", name));
+    };
+
+    let mut values = vec![];
+
+    for arg in spec.args {
+        let text = args.remove(arg.name).unwrap_or_default();
+
+        let words = match tokenize(&text) {
+            Ok(words) => words,
+            Err(e) => return Command::Invalid(format!("<{}> {}", arg.name, e)),
+        };
+
+        if words.is_empty() {
+            if arg.required {
+                return Command::Invalid(format!("missing <{}>", arg.name));
+            }
+
+            values.push(None);
+            continue;
+        }
+
+        match arg.kind.parse(&words, raw(&text, &words)) {
+            Ok(value) => values.push(Some(value)),
+            Err(e) => return Command::Invalid(format!("<{}> {}", arg.name, e)),
+        }
+    }
+
+    (spec.build)(&mut Values(values.into_iter()))
+        .unwrap_or_else(|| Command::Invalid(format!("usage: {}", usage(spec))))
+}
+
+/// the list of commands, or the usage of the command.
+pub(super) fn help(topic: Option<&str>) -> String {
+    let topic = topic.map(|x| x.split_whitespace().collect::<Vec<_>>().join(" "));
+
+    if let Some(topic) = &topic {
+        if let Some(spec) = COMMANDS.iter().find(|x| x.name == topic) {
+            let mut help = format!("{}\n{}", usage(spec), spec.description);
+
+            for arg in spec.args {
+                help += &format!("\n- {}: {}", arg.name, arg.description);
+            }
+
+            return help;
+        }
+
+        let group = COMMANDS
+            .iter()
+            .filter(|x| {
+                x.name
+                    .strip_prefix(topic.as_str())
+                    .is_some_and(|x| x.starts_with(' '))
+            })
+            .map(|x| format!("{}\n  {}", usage(x), x.description))
+            .collect::<Vec<_>>();
+
+        if !group.is_empty() {
+            return group.join("\n");
+        }
+    }
+
+    let mut names = COMMANDS
+        .iter()
+        .map(|x| x.name.split(' ').next().unwrap())
+        .collect::<Vec<_>>();
+
+    names.dedup();
+
+    format!(
+        "usage: `{} <command> [arguments]`\ncommands: {}\nsee `{} help <command>` for details. quote arguments with \"\" to keep spaces.",
+        PREFIX,
+        names.join(", "),
+        PREFIX,
+    )
+}
+
+#[test]
+fn test_parse() {
+    use ControlCommand::*;
+    use PresentationCommand::*;
+
+    let cmd = |x: &str| parse(x).unwrap();
+
+    assert!(parse("hello g!live pause").is_none());
+    assert!(parse("g!livepause").is_none());
+    assert!(matches!(cmd("g!live  pause"), Command::Control(Pause)));
+    assert!(matches!(cmd("g!live"), Command::Help(None)));
+
+    assert!(matches!(
+        cmd("g!live presentations update 2 限界 LT の話"),
+        Command::Control(Presentation(Update { index: 2, new_title })) if new_title == "限界 LT の話"
+    ));
+    assert!(matches!(
+        cmd("g!live presentations update 2 \"two  spaces\""),
+        Command::Control(Presentation(Update { index: 2, new_title })) if new_title == "two  spaces"
+    ));
+    assert!(matches!(
+        cmd("g!live presentations update x title"),
+        Command::Invalid(e) if e == "<index> must be a non-negative integer\nusage: `g!live presentations update <index> <title...>`"
+    ));
+    assert!(matches!(
+        cmd("g!live presentations push <@123> "),
+        Command::Invalid(e) if e.starts_with("missing <title>")
+    ));
+    assert!(matches!(
+        cmd("g!live presentations pop now"),
+        Command::Invalid(e) if e.starts_with("unexpected argument `now`")
+    ));
+
+    assert!(matches!(
+        cmd("g!live remove 42"),
+        Command::Control(TimelineRemove { id: 42 })
+    ));
+
+    assert!(matches!(
+        cmd("g!live mute_user spam bot 10m"),
+        Command::Control(MuteUser { user, duration_secs: Some(600) }) if user == "youtube:spam bot"
+    ));
+    assert!(matches!(
+        cmd("g!live mute_user <@!42>"),
+        Command::Control(MuteUser { user, duration_secs: None }) if user == "discord:42"
+    ));
+
+    assert!(matches!(
+        cmd("g!live tweet -dy ```hello <@1>```"),
+        Command::Control(Tweet {
+            with_youtube_footer: true,
+            with_discord_footer: true,
+            with_twitter_footer: false,
+            msg,
+            simulation: false,
+        }) if msg == "hello <@1>"
+    ));
+    assert!(matches!(
+        cmd("g!live tweet -t hello\n\nworld  again\n"),
+        Command::Control(Tweet { with_twitter_footer: true, msg, .. }) if msg == "hello\n\nworld  again"
+    ));
+    assert!(matches!(
+        cmd("g!live tweet -t hello <@1>"),
+        Command::Invalid(e) if e.starts_with("<message> mentions can't be posted")
+    ));
+    assert!(matches!(
+        cmd("g!live tweet see <#2>"),
+        Command::Invalid(e) if e.starts_with("<message> mentions can't be posted")
+    ));
+    assert!(matches!(
+        cmd("g!live tweet -x hi"),
+        Command::Invalid(e) if e.starts_with(&format!("<footers> {}", FOOTER_FLAGS_HELP))
+    ));
+
+    assert!(matches!(
+        cmd("g!live stream stop"),
+        Command::Control(Obs(ObsCommand::StreamStop { confirmed: false }))
+    ));
+    assert!(matches!(
+        cmd("g!live record stop confirm"),
+        Command::Control(Obs(ObsCommand::RecordStop { confirmed: true }))
+    ));
+    assert!(matches!(
+        cmd("g!live screenshot \"LT main\""),
+        Command::Control(Obs(ObsCommand::Screenshot { scene })) if scene == "LT main"
+    ));
+
+    assert!(matches!(
+        cmd("g!live presentations foo"),
+        Command::Invalid(e) if e.contains("`g!live presentations pop`")
+    ));
+    assert!(help(Some("timer  extend")).starts_with("`g!live timer extend <secs>`"));
+}
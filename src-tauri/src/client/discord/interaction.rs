@@ -0,0 +1,355 @@
+use {
+    super::{
+        command::{self, Kind, Spec, COMMANDS},
+        Command, PREFIX,
+    },
+    crate::control::ControlCommand,
+    serde_json::Value,
+    serenity::{
+        builder::{CreateApplicationCommand, CreateApplicationCommandOption, CreateComponents},
+        model::application::{
+            command::CommandOptionType, component::ButtonStyle,
+            interaction::application_command::CommandDataOption,
+        },
+    },
+    std::collections::HashMap,
+};
+
+/// every subcommand is under `/live`, same as the `g!live` prefix.
+pub(super) const COMMAND_NAME: &str = "live";
+
+/// commands sharing the first word in `COMMANDS` are put in these subcommand groups.
+const GROUPS: &[(&str, &str)] = &[
+    ("presentations", "manage the presentation queue"),
+    ("timer", "control the presentation timer"),
+    ("stream", "control streaming in OBS"),
+    ("record", "control recording in OBS"),
+    ("replay", "control the OBS replay buffer"),
+];
+
+// other kinds are typed as in text commands
+fn option_type(kind: Kind) -> CommandOptionType {
+    match kind {
+        Kind::Mention => CommandOptionType::User,
+        Kind::Integer => CommandOptionType::Integer,
+        _ => CommandOptionType::String,
+    }
+}
+
+fn subcommand_option(name: &str, spec: &Spec) -> CreateApplicationCommandOption {
+    let mut option = CreateApplicationCommandOption::default();
+
+    option
+        .kind(CommandOptionType::SubCommand)
+        .name(name)
+        .description(spec.description);
+
+    // discord wants required options first
+    let args = spec.args.iter().filter(|x| x.required);
+    let args = args.chain(spec.args.iter().filter(|x| !x.required));
+
+    for arg in args {
+        option.create_sub_option(|x| {
+            x.kind(option_type(arg.kind))
+                .name(arg.name)
+                .description(arg.description)
+                .required(arg.required)
+        });
+    }
+
+    option
+}
+
+/// builds `/live` with every command in `COMMANDS`.
+pub(super) fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name(COMMAND_NAME)
+        .description("control the LT screen")
+        .dm_permission(true);
+
+    for spec in COMMANDS.iter().filter(|x| !x.name.contains(' ')) {
+        command.add_option(subcommand_option(spec.name, spec));
+    }
+
+    for (name, description) in GROUPS {
+        let mut group = CreateApplicationCommandOption::default();
+
+        group
+            .kind(CommandOptionType::SubCommandGroup)
+            .name(name)
+            .description(description);
+
+        for spec in COMMANDS {
+            if let Some(sub) = spec
+                .name
+                .strip_prefix(name)
+                .and_then(|x| x.strip_prefix(' '))
+            {
+                group.add_sub_option(subcommand_option(sub, spec));
+            }
+        }
+
+        command.add_option(group);
+    }
+
+    command
+}
+
+/// the names of nested subcommands and the options given to the last one.
+fn flatten(mut options: &[CommandDataOption]) -> (Vec<&str>, &[CommandDataOption]) {
+    let mut path = vec![];
+
+    while let [option] = options {
+        if !matches!(
+            option.kind,
+            CommandOptionType::SubCommand | CommandOptionType::SubCommandGroup
+        ) {
+            break;
+        }
+
+        path.push(option.name.as_str());
+        options = &option.options;
+    }
+
+    (path, options)
+}
+
+/// translates options of `/live` into the same command as the text one.
+pub(super) fn command_of(options: &[CommandDataOption]) -> Command {
+    let (path, options) = flatten(options);
+
+    let args = options
+        .iter()
+        .filter_map(|x| {
+            let text = match (x.kind, x.value.as_ref()?) {
+                // users are given as snowflakes in strings
+                (CommandOptionType::User, Value::String(id)) => format!("<@{}>", id),
+                (_, Value::String(text)) => text.clone(),
+                (_, value) => value.to_string(),
+            };
+
+            Some((x.name.as_str(), text))
+        })
+        .collect::<HashMap<_, _>>();
+
+    command::build(&path.join(" "), args)
+}
+
+// custom IDs of the buttons attached to replies. the rest is a text command without the prefix.
+const BUTTON_PREFIX: &str = "live:";
+
+/// buttons for the operations needed most during the event.
+pub(super) fn control_buttons(components: &mut CreateComponents) -> &mut CreateComponents {
+    const BUTTONS: [(&str, &str, ButtonStyle); 4] = [
+        ("presentations pop", "Pop next", ButtonStyle::Primary),
+        ("pause", "Pause", ButtonStyle::Secondary),
+        ("resume", "Resume", ButtonStyle::Secondary),
+        ("undo", "Undo", ButtonStyle::Danger),
+    ];
+
+    components.create_action_row(|row| {
+        for (command, label, style) in BUTTONS {
+            row.create_button(|b| {
+                b.custom_id(format!("{}{}", BUTTON_PREFIX, command))
+                    .label(label)
+                    .style(style)
+            });
+        }
+
+        row
+    })
+}
+
+/// the button to carry out the command if it has to be confirmed first.
+pub(super) fn confirm_button(cmd: &Command) -> Option<String> {
+    let Command::Control(ControlCommand::Obs(command)) = cmd else {
+        return None;
+    };
+
+    command.needs_confirmation()?;
+
+    // the commands to be confirmed take `confirm` to skip it
+    Some(format!("{}{} confirm", BUTTON_PREFIX, cmd.name()))
+}
+
+pub(super) fn confirm_buttons(
+    custom_id: String,
+) -> impl FnOnce(&mut CreateComponents) -> &mut CreateComponents {
+    move |components| {
+        components.create_action_row(|row| {
+            row.create_button(|b| {
+                b.custom_id(custom_id)
+                    .label("Confirm")
+                    .style(ButtonStyle::Danger)
+            })
+        })
+    }
+}
+
+pub(super) fn command_of_button(custom_id: &str) -> Option<Command> {
+    let command = custom_id.strip_prefix(BUTTON_PREFIX)?;
+    command::parse(&format!("{} {}", PREFIX, command))
+}
+
+#[test]
+fn test_register() {
+    let top_level = COMMANDS.iter().filter(|x| !x.name.contains(' ')).count();
+    assert!(
+        top_level + GROUPS.len() <= 25,
+        "discord allows 25 options at most"
+    );
+
+    for spec in COMMANDS {
+        if let Some((group, _)) = spec.name.split_once(' ') {
+            assert!(
+                GROUPS.iter().any(|(name, _)| *name == group),
+                "no group for {}",
+                spec.name
+            );
+        }
+
+        assert!(spec.description.len() <= 100, "{}", spec.name);
+
+        for arg in spec.args {
+            assert!(arg.description.len() <= 100, "{} {}", spec.name, arg.name);
+        }
+    }
+}
+
+#[test]
+fn test_command_of() {
+    use crate::control::{ObsCommand, PresentationCommand};
+
+    let options = |json: Value| serde_json::from_value::<Vec<CommandDataOption>>(json).unwrap();
+
+    let cmd = command_of(&options(serde_json::json!([{
+        "name": "presentations",
+        "type": 2,
+        "options": [{
+            "name": "push",
+            "type": 1,
+            "options": [
+                { "name": "user", "type": 6, "value": "1234" },
+                { "name": "title", "type": 3, "value": "限界LTについて" },
+            ],
+        }],
+    }])));
+
+    assert!(matches!(
+        cmd,
+        Command::PresentationPush { user_id: 1234, title } if title == "限界LTについて"
+    ));
+
+    let cmd = command_of(&options(serde_json::json!([{
+        "name": "presentations",
+        "type": 2,
+        "options": [{
+            "name": "remove",
+            "type": 1,
+            "options": [{ "name": "index", "type": 4, "value": 3 }],
+        }],
+    }])));
+
+    assert!(matches!(
+        cmd,
+        Command::Control(ControlCommand::Presentation(PresentationCommand::Remove {
+            index: 3
+        }))
+    ));
+
+    let cmd = command_of(&options(serde_json::json!([{
+        "name": "mute_user",
+        "type": 1,
+        "options": [
+            { "name": "user", "type": 3, "value": "荒らし" },
+            { "name": "duration", "type": 3, "value": "10m" },
+        ],
+    }])));
+
+    assert!(matches!(
+        cmd,
+        Command::Control(ControlCommand::MuteUser { user, duration_secs: Some(600) })
+            if user == "youtube:荒らし"
+    ));
+
+    let cmd = command_of(&options(serde_json::json!([{
+        "name": "delete",
+        "type": 1,
+        "options": [{
+            "name": "message",
+            "type": 3,
+            "value": "https://discord.com/channels/1/2/3456",
+        }],
+    }])));
+
+    assert!(matches!(
+        cmd,
+        Command::Delete {
+            message_id: Some(3456)
+        }
+    ));
+
+    let cmd = command_of(&options(serde_json::json!([{
+        "name": "tweet",
+        "type": 1,
+        "options": [
+            { "name": "message", "type": 3, "value": "hello\n  world" },
+            { "name": "footers", "type": 3, "value": "dy" },
+        ],
+    }])));
+
+    assert!(matches!(
+        cmd,
+        Command::Control(ControlCommand::Tweet {
+            with_youtube_footer: true,
+            with_discord_footer: true,
+            with_twitter_footer: false,
+            msg,
+            simulation: false,
+        }) if msg == "hello\n  world"
+    ));
+
+    let cmd = command_of(&options(serde_json::json!([{
+        "name": "tweet",
+        "type": 1,
+        "options": [
+            { "name": "message", "type": 3, "value": "hello" },
+            { "name": "footers", "type": 3, "value": "x" },
+        ],
+    }])));
+
+    assert!(matches!(cmd, Command::Invalid(e) if e.starts_with("<footers> unknown footer flag")));
+
+    let cmd = command_of(&options(serde_json::json!([{
+        "name": "timer",
+        "type": 2,
+        "options": [{ "name": "extend", "type": 1, "options": [] }],
+    }])));
+
+    assert!(matches!(cmd, Command::Invalid(e) if e == "missing <secs>"));
+
+    assert!(matches!(
+        command_of_button("live:undo"),
+        Some(Command::Undo)
+    ));
+    assert!(matches!(
+        command_of_button("live:presentations pop"),
+        Some(Command::Control(ControlCommand::Presentation(
+            PresentationCommand::Pop
+        )))
+    ));
+    assert!(command_of_button("unknown").is_none());
+
+    let stop = Command::Control(ControlCommand::Obs(ObsCommand::StreamStop {
+        confirmed: false,
+    }));
+    let custom_id = confirm_button(&stop).unwrap();
+
+    assert!(matches!(
+        command_of_button(&custom_id),
+        Some(Command::Control(ControlCommand::Obs(
+            ObsCommand::StreamStop { confirmed: true }
+        )))
+    ));
+    assert!(confirm_button(&command_of_button(&custom_id).unwrap()).is_none());
+}
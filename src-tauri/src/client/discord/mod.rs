@@ -0,0 +1,850 @@
+mod command;
+mod interaction;
+mod permission;
+
+use permission::Authorization;
+
+pub(crate) use permission::Permissions;
+
+use {
+    crate::{
+        approval::ApprovalRequest,
+        control::{self, ControlCommand, PresentationCommand},
+        model::{Content, Segment, Service, User},
+        moderation::{self, Incoming},
+        presentations::Presentation,
+        Context,
+    },
+    anyhow::{Context as _, Result},
+    async_trait::async_trait,
+    parking_lot::{Mutex, RwLock},
+    regex::Regex,
+    serenity::{
+        builder::{CreateInteractionResponse, EditInteractionResponse},
+        http::Http,
+        json::{hashmap_to_json_map, Value},
+        model::{
+            application::{
+                command::Command as ApplicationCommand,
+                interaction::{Interaction, InteractionResponseType},
+            },
+            channel::{Message, Reaction, ReactionType},
+            event::MessageUpdateEvent,
+            id::{ChannelId, GuildId, MessageId, UserId},
+            prelude::Ready,
+            user::User as SerenityUser,
+        },
+        prelude::{Client, Context as SerenityContext, EventHandler},
+    },
+    std::{collections::HashMap, sync::Arc},
+    tokio::sync::mpsc::UnboundedReceiver,
+};
+
+use once_cell::sync::Lazy;
+use serenity::prelude::GatewayIntents;
+
+const PREFIX: &str = "g!live";
+
+const APPROVE_REACTION: &str = "✅";
+const REJECT_REACTION: &str = "❌";
+
+static MARKUP_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"(?s)```(?:[\w+-]*\n)?(?P<block>.*?)```",
+        r"|`(?P<inline>[^`]+)`",
+        r"|<(?P<animated>a?):(?P<emoji>\w+):(?P<emoji_id>\d+)>",
+        r"|(?P<ref><(?:@!?|@&|#)\d+>)",
+    ))
+    .unwrap()
+});
+
+/// converts discord markup into segments.
+/// `names` maps raw mention tokens (e.g. `<@123>`) to display text resolved beforehand.
+fn parse_discord_content(text: &str, names: &HashMap<String, String>) -> Content {
+    let mut content = Content::new();
+    let mut last = 0;
+
+    for caps in MARKUP_REGEX.captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        content.push_text_with_links(&text[last..whole.start()]);
+        last = whole.end();
+
+        if let Some(code) = caps.name("block").or_else(|| caps.name("inline")) {
+            content.push(Segment::Code {
+                text: code.as_str().trim_matches('\n').to_string(),
+            });
+        } else if let Some(emoji) = caps.name("emoji") {
+            let ext = if caps["animated"].is_empty() {
+                "png"
+            } else {
+                "gif"
+            };
+
+            content.push(Segment::Emoji {
+                url: format!(
+                    "https://cdn.discordapp.com/emojis/{}.{}",
+                    &caps["emoji_id"], ext
+                ),
+                alt: format!(":{}:", emoji.as_str()),
+            });
+        } else if let Some(reference) = caps.name("ref") {
+            match names.get(reference.as_str()) {
+                Some(name) => content.push(Segment::Mention { name: name.clone() }),
+                None => content.push_text(reference.as_str()),
+            }
+        }
+    }
+
+    content.push_text_with_links(&text[last..]);
+    content
+}
+
+#[test]
+fn test_parse_discord_content() {
+    let names = [("<@!1>", "@alice"), ("<#2>", "#general")]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+    let content = parse_discord_content(
+        "<@!1> see <#2> <:wave:3><a:dance:4> `x + 1` https://example.com <@5>",
+        &names,
+    );
+
+    assert_eq!(
+        content.segments(),
+        [
+            Segment::Mention {
+                name: "@alice".into()
+            },
+            Segment::Text {
+                text: " see ".into()
+            },
+            Segment::Mention {
+                name: "#general".into()
+            },
+            Segment::Text { text: " ".into() },
+            Segment::Emoji {
+                url: "https://cdn.discordapp.com/emojis/3.png".into(),
+                alt: ":wave:".into()
+            },
+            Segment::Emoji {
+                url: "https://cdn.discordapp.com/emojis/4.gif".into(),
+                alt: ":dance:".into()
+            },
+            Segment::Text { text: " ".into() },
+            Segment::Code {
+                text: "x + 1".into()
+            },
+            Segment::Text { text: " ".into() },
+            Segment::Link {
+                url: "https://example.com".into(),
+                text: "https://example.com".into()
+            },
+            Segment::Text {
+                text: " <@5>".into()
+            },
+        ]
+    );
+}
+
+/// resolves display names of the mentions, roles and channels referenced in a message.
+async fn mention_names(ctx: &SerenityContext, message: &Message) -> HashMap<String, String> {
+    static CHANNEL_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<#(\d+)>").unwrap());
+
+    let mut names = HashMap::new();
+
+    for user in &message.mentions {
+        let name = match message.guild_id {
+            Some(guild_id) => user.nick_in(ctx, guild_id).await,
+            None => None,
+        }
+        .unwrap_or_else(|| user.name.clone());
+
+        names.insert(format!("<@{}>", user.id), format!("@{}", name));
+        names.insert(format!("<@!{}>", user.id), format!("@{}", name));
+    }
+
+    for role_id in &message.mention_roles {
+        let name = role_id
+            .to_role_cached(&ctx.cache)
+            .map(|r| r.name)
+            .unwrap_or_else(|| "role".to_string());
+
+        names.insert(format!("<@&{}>", role_id), format!("@{}", name));
+    }
+
+    for caps in CHANNEL_REGEX.captures_iter(&message.content) {
+        let Ok(id) = caps[1].parse::<u64>() else {
+            continue;
+        };
+
+        let name = ChannelId(id)
+            .name(&ctx.cache)
+            .await
+            .unwrap_or_else(|| "channel".to_string());
+
+        names.insert(caps[0].to_string(), format!("#{}", name));
+    }
+
+    names
+}
+
+enum Command {
+    // the usage of the command if given
+    Help(Option<String>),
+    // the reply explaining what is wrong
+    Invalid(String),
+    Listen,
+    StopListening,
+    // removes the message from the timeline. the one which the command replies to if None.
+    Delete { message_id: Option<u64> },
+    PresentationPush { user_id: u64, title: String },
+    Undo,
+    Control(ControlCommand),
+}
+
+/// where a command was invoked, either by a text message or an interaction.
+struct Invocation {
+    channel_id: ChannelId,
+    guild_id: Option<GuildId>,
+    // the message which the command replies to
+    replied: Option<MessageId>,
+}
+
+impl Invocation {
+    fn of_message(message: &Message) -> Self {
+        Self {
+            channel_id: message.channel_id,
+            guild_id: message.guild_id,
+            replied: message
+                .message_reference
+                .as_ref()
+                .and_then(|x| x.message_id),
+        }
+    }
+}
+
+struct DiscordListenerInner {
+    my_id: Option<u64>,
+}
+
+pub struct DiscordListener {
+    inner: RwLock<DiscordListenerInner>,
+    ctx: Arc<Context>,
+
+    // where operators approve or reject messages in the approval queue
+    approval_channel_id: Option<u64>,
+    // message ID in the approval channel -> ID in the approval queue
+    approval_messages: Arc<Mutex<HashMap<u64, u64>>>,
+
+    // where alerts are posted for operators
+    alert_channel_id: Option<u64>,
+
+    permissions: Permissions,
+}
+
+impl DiscordListener {
+    pub(crate) fn new(
+        ctx: Arc<Context>,
+        approval_channel_id: Option<u64>,
+        alert_channel_id: Option<u64>,
+        permissions: Permissions,
+    ) -> Self {
+        Self {
+            ctx,
+            inner: RwLock::new(DiscordListenerInner { my_id: None }),
+            approval_channel_id,
+            approval_messages: Arc::new(Mutex::new(HashMap::new())),
+            alert_channel_id,
+            permissions,
+        }
+    }
+
+    pub async fn start(self, token: &str) -> Result<()> {
+        Client::builder(token, GatewayIntents::all())
+            .event_handler(self)
+            .await
+            .context("Failed to create discord client")?
+            .start()
+            .await
+            .context("Failed to start discord client")
+    }
+
+    async fn invoke_command(&self, ctx: &SerenityContext, message: &Message, cmd: Command) {
+        let with_buttons = !matches!(cmd, Command::Help(_) | Command::Invalid(_));
+        let confirm = interaction::confirm_button(&cmd);
+        let text = self
+            .command_output(cmd, &Invocation::of_message(message), ctx)
+            .await;
+
+        let result = message
+            .channel_id
+            .send_message(&ctx, |m| {
+                m.content(&text);
+
+                if let Some(custom_id) = confirm {
+                    m.components(interaction::confirm_buttons(custom_id));
+                } else if with_buttons {
+                    m.components(interaction::control_buttons);
+                }
+
+                m
+            })
+            .await;
+
+        if let Err(e) = result {
+            tracing::error!("failed to send message!: {:?}\n{}", e, text);
+        }
+    }
+
+    async fn command_output(
+        &self,
+        cmd: Command,
+        invocation: &Invocation,
+        ctx: &SerenityContext,
+    ) -> String {
+        use Command::*;
+
+        match cmd {
+            Help(topic) => command::help(topic.as_deref()),
+
+            Invalid(reply) => reply,
+
+            Listen => {
+                if invocation.guild_id.is_none() {
+                    return "listen works only in server channels".into();
+                }
+
+                let chan = invocation.channel_id;
+                *self.ctx.listening_channel_id.write().await = Some(chan.0);
+                self.ctx.state_file.request_save();
+
+                let text_buffer = format!("now listening at <#{}>", chan.0);
+                tracing::info!("{}", text_buffer);
+                text_buffer
+            }
+
+            Delete { message_id } => {
+                let Some(message_id) = message_id.or(invocation.replied.map(|x| x.0)) else {
+                    return "reply to the message to delete with this command".into();
+                };
+
+                let id = self
+                    .ctx
+                    .screen_state
+                    .read()
+                    .await
+                    .find_by_source(Service::Discord, &message_id.to_string());
+
+                match id {
+                    Some(id) => {
+                        control::execute(&self.ctx, ControlCommand::TimelineRemove { id }).await
+                    }
+                    None => "the message is not on the timeline".into(),
+                }
+            }
+
+            StopListening => if self.ctx.listening_channel_id.write().await.take().is_some() {
+                self.ctx.state_file.request_save();
+                "stopped"
+            } else {
+                "currently not listening any channel"
+            }
+            .into(),
+
+            PresentationPush {
+                user_id: uid,
+                title,
+            } => {
+                let user = match UserId(uid).to_user(&ctx).await {
+                    Ok(u) => u,
+                    Err(e) => {
+                        tracing::error!("failed to fetch user info. id: {}, err: {}", uid, e);
+                        return "couldn't get user info. please check user mention is correct. read log for more info.".into();
+                    }
+                };
+
+                let icon = user.avatar_url();
+                // the guild in config for DMs
+                let name = match invocation.guild_id.or(self.permissions.guild_id()) {
+                    Some(gid) => user.nick_in(&ctx.http, gid).await.unwrap_or(user.name),
+                    None => user.name,
+                };
+
+                let presentation = Presentation {
+                    presenter: User {
+                        icon,
+                        ident: None,
+                        name,
+                    },
+                    title,
+                    duration_secs: None,
+                };
+
+                control::execute(
+                    &self.ctx,
+                    ControlCommand::Presentation(PresentationCommand::Push(presentation)),
+                )
+                .await
+            }
+
+            Undo => control::undo(&self.ctx).await,
+
+            Control(cmd) => control::execute(&self.ctx, cmd).await,
+        }
+    }
+
+    async fn is_listening(&self, channel_id: ChannelId) -> bool {
+        *self.ctx.listening_channel_id.read().await == Some(channel_id.0)
+    }
+
+    /// checks permissions. failures are logged and the user is denied.
+    async fn authorize(
+        &self,
+        ctx: &SerenityContext,
+        user: &SerenityUser,
+        command: &str,
+    ) -> Authorization {
+        match self.permissions.authorize(ctx, user, command).await {
+            Ok(x) => x,
+            Err(e) => {
+                tracing::warn!(
+                    "failed to check whether user {} can run {}: {:?}",
+                    user.name,
+                    command,
+                    e
+                );
+                Authorization::Denied
+            }
+        }
+    }
+}
+
+/// converts a message in the listened channel into a timeline item.
+async fn incoming_of(ctx: &SerenityContext, message: &Message) -> Incoming {
+    let content = parse_discord_content(message.content.trim(), &mention_names(ctx, message).await);
+
+    let name = message
+        .author_nick(ctx)
+        .await
+        .unwrap_or_else(|| message.author.name.clone());
+
+    Incoming {
+        service: Service::Discord,
+        author: message.author.id.to_string(),
+        source_id: Some(message.id.to_string()),
+        user: User {
+            icon: message.author.avatar_url(),
+            ident: None,
+            name,
+        },
+        content,
+        highlight: None,
+    }
+}
+
+/// posts each message in the approval queue with reactions to approve or reject it.
+async fn post_approval_requests(
+    http: Arc<Http>,
+    channel_id: ChannelId,
+    mut requests: UnboundedReceiver<ApprovalRequest>,
+    messages: Arc<Mutex<HashMap<u64, u64>>>,
+) {
+    while let Some(request) = requests.recv().await {
+        let result = channel_id
+            .send_message(&http, |m| {
+                m.content(&request.summary)
+                    .allowed_mentions(|x| x.empty_parse())
+                    .reactions(
+                        [APPROVE_REACTION, REJECT_REACTION]
+                            .map(|x| ReactionType::Unicode(x.to_string())),
+                    )
+            })
+            .await;
+
+        match result {
+            Ok(message) => {
+                messages.lock().insert(message.id.0, request.id);
+            }
+
+            Err(e) => {
+                tracing::error!(
+                    "failed to post approval request. it's never shown: {:?}\n{}",
+                    e,
+                    request.summary
+                );
+            }
+        }
+
+        if let Some(old) = request.supersedes {
+            close_approval_message(&http, channel_id, &messages, old).await;
+        }
+    }
+}
+
+/// marks the message asking for the request as superseded, so that operators don't use it.
+async fn close_approval_message(
+    http: &Http,
+    channel_id: ChannelId,
+    messages: &Mutex<HashMap<u64, u64>>,
+    request_id: u64,
+) {
+    let message_id = {
+        let mut messages = messages.lock();
+        let Some(message_id) = messages
+            .iter()
+            .find(|(_, x)| **x == request_id)
+            .map(|(x, _)| *x)
+        else {
+            return;
+        };
+
+        messages.remove(&message_id);
+        message_id
+    };
+
+    let result = channel_id
+        .edit_message(http, message_id, |m| {
+            m.content("✏️ superseded by the edit below")
+        })
+        .await;
+
+    if let Err(e) = result {
+        tracing::warn!("failed to update approval message: {}", e);
+    }
+
+    if let Err(e) = http
+        .delete_message_reactions(channel_id.0, message_id)
+        .await
+    {
+        tracing::warn!("failed to remove reactions of approval message: {}", e);
+    }
+}
+
+async fn post_alerts(
+    http: Arc<Http>,
+    channel_id: ChannelId,
+    mut alerts: UnboundedReceiver<String>,
+) {
+    while let Some(alert) = alerts.recv().await {
+        let result = channel_id
+            .send_message(&http, |m| {
+                m.content(format!("⚠️ {}", alert))
+                    .allowed_mentions(|x| x.empty_parse())
+            })
+            .await;
+
+        if let Err(e) = result {
+            tracing::error!("failed to post alert: {:?}\n{}", e, alert);
+        }
+    }
+}
+
+#[async_trait]
+impl EventHandler for DiscordListener {
+    async fn ready(&self, ctx: SerenityContext, ready: Ready) {
+        tracing::info!("DiscordBot({}) is connected!", ready.user.name);
+        self.inner.write().my_id = Some(ready.user.id.0);
+
+        // ready is sent again on reconnection. the requests are already being posted then.
+        if let (Some(channel_id), Some(requests)) =
+            (self.approval_channel_id, self.ctx.approval.take_requests())
+        {
+            tokio::spawn(post_approval_requests(
+                Arc::clone(&ctx.http),
+                ChannelId(channel_id),
+                requests,
+                Arc::clone(&self.approval_messages),
+            ));
+        }
+
+        if let (Some(channel_id), Some(alerts)) =
+            (self.alert_channel_id, self.ctx.alerts.take_receiver())
+        {
+            tokio::spawn(post_alerts(
+                Arc::clone(&ctx.http),
+                ChannelId(channel_id),
+                alerts,
+            ));
+        }
+
+        // global commands are also available in DMs
+        let result = ApplicationCommand::set_global_application_commands(&ctx.http, |c| {
+            c.create_application_command(interaction::register)
+        })
+        .await;
+
+        if let Err(e) = result {
+            tracing::warn!("failed to register slash commands: {}", e);
+        }
+    }
+
+    async fn interaction_create(&self, ctx: SerenityContext, interaction: Interaction) {
+        let (cmd, user, invocation, interaction_id, token) = match &interaction {
+            Interaction::ApplicationCommand(x) if x.data.name == interaction::COMMAND_NAME => (
+                interaction::command_of(&x.data.options),
+                &x.user,
+                Invocation {
+                    channel_id: x.channel_id,
+                    guild_id: x.guild_id,
+                    replied: None,
+                },
+                x.id,
+                &x.token,
+            ),
+
+            Interaction::MessageComponent(x) => {
+                let Some(cmd) = interaction::command_of_button(&x.data.custom_id) else {
+                    return;
+                };
+
+                (
+                    cmd,
+                    &x.user,
+                    Invocation {
+                        channel_id: x.channel_id,
+                        guild_id: x.guild_id,
+                        replied: None,
+                    },
+                    x.id,
+                    &x.token,
+                )
+            }
+
+            _ => return,
+        };
+
+        let refusal = match self.authorize(&ctx, user, cmd.name()).await {
+            Authorization::Allowed => None,
+            Authorization::Requires(level) => Some(format!("only {}s can run this command", level)),
+            Authorization::Denied => Some("you are not allowed to use this command".into()),
+        };
+        let allowed = refusal.is_none();
+
+        // both kinds of interactions are answered in the same way, so builders are sent through http directly
+        let mut response = CreateInteractionResponse::default();
+
+        match refusal {
+            None => {
+                response.kind(InteractionResponseType::DeferredChannelMessageWithSource);
+            }
+            Some(refusal) => {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|d| d.content(refusal).ephemeral(true));
+            }
+        }
+
+        let result = ctx
+            .http
+            .create_interaction_response(
+                interaction_id.0,
+                token,
+                &Value::from(hashmap_to_json_map(response.0)),
+            )
+            .await;
+
+        if let Err(e) = result {
+            tracing::error!("failed to respond to interaction: {:?}", e);
+            return;
+        }
+
+        if !allowed {
+            return;
+        }
+
+        let with_buttons = !matches!(cmd, Command::Help(_) | Command::Invalid(_));
+        let confirm = interaction::confirm_button(&cmd);
+        let text = self.command_output(cmd, &invocation, &ctx).await;
+
+        let mut edit = EditInteractionResponse::default();
+        edit.content(&text);
+
+        if let Some(custom_id) = confirm {
+            edit.components(interaction::confirm_buttons(custom_id));
+        } else if with_buttons {
+            edit.components(interaction::control_buttons);
+        }
+
+        let result = ctx
+            .http
+            .edit_original_interaction_response(token, &Value::from(hashmap_to_json_map(edit.0)))
+            .await;
+
+        if let Err(e) = result {
+            tracing::error!("failed to send message!: {:?}\n{}", e, text);
+        }
+    }
+
+    async fn reaction_add(&self, ctx: SerenityContext, reaction: Reaction) {
+        if self.approval_channel_id != Some(reaction.channel_id.0)
+            || reaction.user_id.map(|x| x.0) == self.inner.read().my_id
+        {
+            return;
+        }
+
+        let approve = match &reaction.emoji {
+            ReactionType::Unicode(x) if x == APPROVE_REACTION => true,
+            ReactionType::Unicode(x) if x == REJECT_REACTION => false,
+            _ => return,
+        };
+
+        let user = match reaction.user(&ctx).await {
+            Ok(u) => u,
+            Err(e) => {
+                tracing::warn!("failed to get the user who reacted: {}", e);
+                return;
+            }
+        };
+
+        if !matches!(
+            self.authorize(&ctx, &user, permission::APPROVE).await,
+            Authorization::Allowed
+        ) {
+            return;
+        }
+
+        let Some(id) = self.approval_messages.lock().remove(&reaction.message_id.0) else {
+            return;
+        };
+
+        let Some(incoming) = self.ctx.approval.take(id) else {
+            let result = reaction
+                .channel_id
+                .edit_message(&ctx, reaction.message_id, |m| {
+                    m.content("no longer pending. the message was edited or deleted by the author")
+                })
+                .await;
+
+            if let Err(e) = result {
+                tracing::warn!("failed to update approval message: {}", e);
+            }
+
+            return;
+        };
+
+        let summary = incoming.describe();
+
+        let verdict = if approve {
+            moderation::push(&self.ctx, incoming).await;
+            "approved"
+        } else {
+            "rejected"
+        };
+
+        tracing::info!("{} by {}: {}", verdict, user.name, summary);
+
+        let result = reaction
+            .channel_id
+            .edit_message(&ctx, reaction.message_id, |m| {
+                m.content(format!(
+                    "{} {} by {}\n{}",
+                    if approve {
+                        APPROVE_REACTION
+                    } else {
+                        REJECT_REACTION
+                    },
+                    verdict,
+                    user.name,
+                    summary
+                ))
+            })
+            .await;
+
+        if let Err(e) = result {
+            tracing::warn!("failed to update approval message: {}", e);
+        }
+    }
+
+    async fn message(&self, ctx: SerenityContext, message: Message) {
+        if self.inner.read().my_id == Some(message.author.id.0) {
+            return;
+        }
+
+        let content = message.content.trim();
+
+        if let Some(cmd) = command::parse(content) {
+            match self.authorize(&ctx, &message.author, cmd.name()).await {
+                Authorization::Allowed => {
+                    self.invoke_command(&ctx, &message, cmd).await;
+                    return;
+                }
+
+                Authorization::Requires(level) => {
+                    let reply = format!("only {}s can run `{}`", level, cmd.name());
+
+                    if let Err(e) = message.channel_id.say(&ctx, reply).await {
+                        tracing::error!("failed to send message!: {:?}", e);
+                    }
+
+                    return;
+                }
+
+                // not a command for this bot. it may be shown on the timeline.
+                Authorization::Denied => {}
+            }
+        }
+
+        if !self.is_listening(message.channel_id).await {
+            return;
+        }
+
+        moderation::submit(&self.ctx, incoming_of(&ctx, &message).await).await;
+    }
+
+    async fn message_update(
+        &self,
+        ctx: SerenityContext,
+        _: Option<Message>,
+        new: Option<Message>,
+        event: MessageUpdateEvent,
+    ) {
+        // embeds being resolved are also notified as updates
+        if event.content.is_none() || !self.is_listening(event.channel_id).await {
+            return;
+        }
+
+        let message = match new {
+            Some(m) => m,
+            None => match event.channel_id.message(&ctx, event.id).await {
+                Ok(m) => m,
+                Err(e) => {
+                    tracing::warn!("failed to fetch edited message {}: {}", event.id, e);
+                    return;
+                }
+            },
+        };
+
+        moderation::submit_edit(&self.ctx, incoming_of(&ctx, &message).await).await;
+    }
+
+    async fn message_delete(
+        &self,
+        _: SerenityContext,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        _: Option<GuildId>,
+    ) {
+        if self.is_listening(channel_id).await {
+            moderation::remove_by_source(&self.ctx, Service::Discord, &message_id.to_string())
+                .await;
+        }
+    }
+
+    async fn message_delete_bulk(
+        &self,
+        _: SerenityContext,
+        channel_id: ChannelId,
+        message_ids: Vec<MessageId>,
+        _: Option<GuildId>,
+    ) {
+        if !self.is_listening(channel_id).await {
+            return;
+        }
+
+        for id in message_ids {
+            moderation::remove_by_source(&self.ctx, Service::Discord, &id.to_string()).await;
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use {
+    super::command,
+    crate::optional_env_var,
+    anyhow::{bail, Context as _, Result},
+    serenity::{
+        model::{id::GuildId, user::User},
+        prelude::Context as SerenityContext,
+    },
+    std::{collections::HashMap, fmt},
+};
+
+/// operators run commands needed during the event. organizers can run everything.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) enum Level {
+    Operator,
+    Organizer,
+}
+
+impl Level {
+    fn parse(s: &str) -> Result<Self> {
+        match s.trim() {
+            "operator" => Ok(Self::Operator),
+            "organizer" => Ok(Self::Organizer),
+            _ => bail!("unknown permission level {}. use operator or organizer", s),
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Operator => "operator",
+            Self::Organizer => "organizer",
+        })
+    }
+}
+
+/// commands which change what is posted or shown outside of the LT
+const ORGANIZER_COMMANDS: &[&str] = &[
+    "tweet",
+    "presentation_tweet",
+    "youtube",
+    "listen",
+    "stop_listening",
+    "stream start",
+    "stream stop",
+];
+
+/// reacting to messages in the approval channel
+pub(super) const APPROVE: &str = "approve";
+
+pub(super) enum Authorization {
+    Allowed,
+    /// the user can run some commands but not this one
+    Requires(Level),
+    Denied,
+}
+
+/// who can run which commands. roles are looked up in the guild, so they also work in DMs.
+pub(crate) struct Permissions {
+    guild_id: Option<GuildId>,
+    roles: HashMap<u64, Level>,
+    users: HashMap<u64, Level>,
+    // command name -> level, overriding the default
+    commands: HashMap<String, Level>,
+}
+
+fn ids_from_env(name: &str) -> Result<Vec<u64>> {
+    optional_env_var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(|x| {
+            x.parse()
+                .with_context(|| format!("failed to decode {}", name))
+        })
+        .collect()
+}
+
+/// "tweet=organizer, presentations pop=operator"
+fn parse_command_levels(s: &str) -> Result<HashMap<String, Level>> {
+    s.split(',')
+        .filter(|x| !x.trim().is_empty())
+        .map(|x| {
+            let Some((name, level)) = x.split_once('=') else {
+                bail!("{} must be like <command>=<level>", x.trim());
+            };
+
+            let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+
+            if !command::names().chain([APPROVE]).any(|x| x == name) {
+                bail!("unknown command {}", name);
+            }
+
+            Ok((name, Level::parse(level)?))
+        })
+        .collect()
+}
+
+impl Permissions {
+    pub(crate) fn from_env() -> Result<Self> {
+        let guild_id = optional_env_var("DISCORD_GUILD_ID")
+            .map(|x| x.parse().map(GuildId))
+            .transpose()
+            .context("failed to decode DISCORD_GUILD_ID")?;
+
+        let levels = |organizers: &str, operators: &str| -> Result<HashMap<u64, Level>> {
+            let mut map = HashMap::new();
+
+            // organizers win when someone is in both
+            for id in ids_from_env(operators)? {
+                map.insert(id, Level::Operator);
+            }
+
+            for id in ids_from_env(organizers)? {
+                map.insert(id, Level::Organizer);
+            }
+
+            Ok(map)
+        };
+
+        let permissions = Self {
+            guild_id,
+            roles: levels("DISCORD_ORGANIZER_ROLE_IDS", "DISCORD_OPERATOR_ROLE_IDS")?,
+            users: levels("DISCORD_ORGANIZER_USER_IDS", "DISCORD_OPERATOR_USER_IDS")?,
+            commands: parse_command_levels(
+                &optional_env_var("DISCORD_COMMAND_LEVELS").unwrap_or_default(),
+            )
+            .context("failed to decode DISCORD_COMMAND_LEVELS")?,
+        };
+
+        if guild_id.is_none() && !permissions.roles.is_empty() {
+            bail!("DISCORD_GUILD_ID is required to check roles");
+        }
+
+        if permissions.roles.is_empty() && permissions.users.is_empty() {
+            tracing::warn!("no one can run discord commands. set roles or users to allow");
+        }
+
+        Ok(permissions)
+    }
+
+    /// the guild where commands are used. also used for DMs.
+    pub(super) fn guild_id(&self) -> Option<GuildId> {
+        self.guild_id
+    }
+
+    fn required(&self, command: &str) -> Level {
+        match self.commands.get(command) {
+            Some(level) => *level,
+            None if ORGANIZER_COMMANDS.contains(&command) => Level::Organizer,
+            None => Level::Operator,
+        }
+    }
+
+    async fn level_of(&self, ctx: &SerenityContext, user: &User) -> Result<Option<Level>> {
+        let by_user = self.users.get(&user.id.0).copied();
+
+        let Some(guild_id) = self.guild_id.filter(|_| by_user != Some(Level::Organizer)) else {
+            return Ok(by_user);
+        };
+
+        if self.roles.is_empty() {
+            return Ok(by_user);
+        }
+
+        let member = match guild_id.member(ctx, user.id).await {
+            Ok(member) => member,
+            // e.g. users allowed individually who are not in the guild
+            Err(_) if by_user.is_some() => return Ok(by_user),
+            Err(e) => return Err(e).context("failed to get guild member"),
+        };
+
+        let by_role = member
+            .roles
+            .iter()
+            .filter_map(|x| self.roles.get(&x.0).copied())
+            .max();
+
+        Ok(by_user.max(by_role))
+    }
+
+    /// whether the user can run the command, named as in `help`.
+    pub(super) async fn authorize(
+        &self,
+        ctx: &SerenityContext,
+        user: &User,
+        command: &str,
+    ) -> Result<Authorization> {
+        let required = self.required(command);
+
+        Ok(match self.level_of(ctx, user).await? {
+            Some(level) if level >= required => Authorization::Allowed,
+            Some(_) => Authorization::Requires(required),
+            None => Authorization::Denied,
+        })
+    }
+}
+
+#[test]
+fn test_parse_command_levels() {
+    let levels = parse_command_levels("tweet=operator, presentations  pop = organizer,").unwrap();
+    assert_eq!(levels["tweet"], Level::Operator);
+    assert_eq!(levels["presentations pop"], Level::Organizer);
+
+    assert!(parse_command_levels("").unwrap().is_empty());
+    assert!(parse_command_levels("tweet").is_err());
+    assert!(parse_command_levels("unknown=operator").is_err());
+    assert!(parse_command_levels("tweet=admin").is_err());
+}
@@ -3,3 +3,9 @@ pub mod discord;
 
 #[cfg(feature = "youtube")]
 pub mod youtube;
+
+#[cfg(feature = "twitter")]
+pub mod twitter;
+
+#[cfg(feature = "control_server")]
+pub mod control_server;
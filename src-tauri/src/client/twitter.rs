@@ -0,0 +1,362 @@
+use {
+    crate::{
+        model::{Content, Service, User},
+        moderation::{self, Incoming},
+        Context,
+    },
+    anyhow::{Context as _, Result},
+    chrono::{DateTime, SecondsFormat, Utc},
+    serde::Deserialize,
+    std::{collections::HashMap, sync::Arc, time::Duration},
+    tokio::time::sleep,
+};
+
+const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    #[serde(default)]
+    data: Vec<Tweet>,
+    #[serde(default)]
+    includes: Includes,
+    meta: Meta,
+}
+
+#[derive(Deserialize)]
+struct Tweet {
+    id: String,
+    text: String,
+    author_id: String,
+}
+
+#[derive(Deserialize, Default)]
+struct Includes {
+    #[serde(default)]
+    users: Vec<TwitterUser>,
+}
+
+#[derive(Deserialize)]
+struct TwitterUser {
+    id: String,
+    name: String,
+    username: String,
+    profile_image_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Meta {
+    newest_id: Option<String>,
+    // set when more results are left in older pages
+    next_token: Option<String>,
+}
+
+// v2 API escapes only these in `text`
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// converts a response into timeline items, oldest first.
+/// also returns the meta to fetch the next page or only newer ones next time.
+fn parse_search_response(json: &str) -> Result<(Vec<Incoming>, Meta)> {
+    let response =
+        serde_json::from_str::<SearchResponse>(json).context("failed to decode response")?;
+
+    let users = response
+        .includes
+        .users
+        .into_iter()
+        .map(|x| (x.id.clone(), x))
+        .collect::<HashMap<_, _>>();
+
+    let tweets = response
+        .data
+        .into_iter()
+        .rev()
+        .map(|tweet| {
+            let user = match users.get(&tweet.author_id) {
+                Some(u) => User {
+                    icon: u.profile_image_url.clone(),
+                    ident: Some(u.username.clone()),
+                    name: u.name.clone(),
+                },
+
+                None => User {
+                    icon: None,
+                    ident: None,
+                    name: tweet.author_id.clone(),
+                },
+            };
+
+            Incoming {
+                service: Service::Twitter,
+                author: user.ident.clone().unwrap_or(tweet.author_id),
+                source_id: Some(tweet.id),
+                user,
+                content: Content::from_text(&unescape(&tweet.text)),
+                highlight: None,
+            }
+        })
+        .collect();
+
+    Ok((tweets, response.meta))
+}
+
+fn backoff(interval: Duration, retry: u32) -> Duration {
+    interval
+        .checked_mul(2u32.saturating_pow(retry))
+        .map_or(MAX_BACKOFF, |x| x.min(MAX_BACKOFF))
+}
+
+/// polls v2 recent search for tweets with the hashtag and pushes them to the timeline.
+pub(crate) struct TwitterListener {
+    ctx: Arc<Context>,
+    http: reqwest::Client,
+    api_base: String,
+    bearer_token: String,
+    query: String,
+    interval: Duration,
+
+    // tweets before the app started are not shown
+    started_at: DateTime<Utc>,
+    since_id: Option<String>,
+}
+
+impl TwitterListener {
+    pub(crate) fn new(
+        ctx: Arc<Context>,
+        api_base: String,
+        bearer_token: String,
+        hashtag: &str,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            ctx,
+            http: reqwest::Client::new(),
+            api_base: api_base.trim_end_matches('/').to_string(),
+            bearer_token,
+            query: format!("{} -is:retweet", hashtag),
+            interval,
+            started_at: Utc::now(),
+            since_id: None,
+        }
+    }
+
+    pub(crate) async fn start(mut self) {
+        let mut retry = 0;
+
+        loop {
+            let wait = match self.poll().await {
+                Ok(tweets) => {
+                    retry = 0;
+                    self.push(tweets).await;
+                    self.interval
+                }
+
+                Err(e) => {
+                    let wait = backoff(self.interval, retry);
+                    retry += 1;
+
+                    tracing::error!("failed to search tweets. retrying in {:?}: {:?}", wait, e);
+
+                    wait
+                }
+            };
+
+            sleep(wait).await;
+        }
+    }
+
+    /// fetches every page of new tweets, oldest first.
+    async fn poll(&mut self) -> Result<Vec<Incoming>> {
+        let mut pages = vec![];
+        let mut newest_id = None;
+        let mut next_token = None;
+
+        // pages go from newer to older. since_id must not move on until all of them are read,
+        // or the tweets left in older pages would be skipped.
+        loop {
+            let (tweets, meta) = self.fetch_page(next_token.as_deref()).await?;
+            pages.push(tweets);
+
+            // only the first page has the newest of all
+            if newest_id.is_none() {
+                newest_id = meta.newest_id;
+            }
+
+            match meta.next_token {
+                Some(token) => next_token = Some(token),
+                None => break,
+            }
+        }
+
+        if newest_id.is_some() {
+            self.since_id = newest_id;
+        }
+
+        Ok(pages.into_iter().rev().flatten().collect())
+    }
+
+    async fn fetch_page(&self, next_token: Option<&str>) -> Result<(Vec<Incoming>, Meta)> {
+        let mut query = vec![
+            ("query", self.query.clone()),
+            ("max_results", "100".into()),
+            ("expansions", "author_id".into()),
+            ("user.fields", "name,username,profile_image_url".into()),
+        ];
+
+        match &self.since_id {
+            Some(id) => query.push(("since_id", id.clone())),
+            None => query.push((
+                "start_time",
+                self.started_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            )),
+        }
+
+        if let Some(token) = next_token {
+            query.push(("next_token", token.to_string()));
+        }
+
+        let response = self
+            .http
+            .get(format!("{}/2/tweets/search/recent", self.api_base))
+            .bearer_auth(&self.bearer_token)
+            .query(&query)
+            .send()
+            .await
+            .context("failed to send request")?;
+
+        let status = response.status();
+        let body = response.text().await.context("failed to read response")?;
+
+        if !status.is_success() {
+            anyhow::bail!("twitter returned {}: {}", status, body);
+        }
+
+        parse_search_response(&body)
+    }
+
+    async fn push(&self, tweets: Vec<Incoming>) {
+        for tweet in tweets {
+            moderation::submit(&self.ctx, tweet).await;
+        }
+    }
+}
+
+#[test]
+fn test_parse_search_response() {
+    let (tweets, meta) =
+        parse_search_response(include_str!("fixtures/twitter_search_recent.json")).unwrap();
+
+    assert_eq!(meta.newest_id.as_deref(), Some("1714528311742505303"));
+    assert!(meta.next_token.is_none());
+
+    let contents = tweets
+        .iter()
+        .map(|x| (x.author.as_str(), x.content.plain_text()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        contents,
+        [
+            ("XDevelopers", "はじまった！ #限界LT".to_string()),
+            (
+                "genkai_otaku",
+                "2本目の発表おもしろい & ためになる #限界LT".to_string()
+            ),
+        ]
+    );
+
+    let (tweets, meta) = parse_search_response(r#"{"meta":{"result_count":0}}"#).unwrap();
+    assert!(tweets.is_empty());
+    assert!(meta.newest_id.is_none());
+
+    assert_eq!(backoff(Duration::from_secs(10), 0), Duration::from_secs(10));
+    assert_eq!(backoff(Duration::from_secs(10), 2), Duration::from_secs(40));
+    assert_eq!(backoff(Duration::from_secs(10), 40), MAX_BACKOFF);
+}
+
+#[test]
+fn test_poll() {
+    let ctx = Context::for_test(None);
+
+    ctx.rt.block_on(async {
+        let (base, server) = crate::test_util::mock_http_server(vec![
+            (200, include_str!("fixtures/twitter_search_recent.json")),
+            (429, r#"{"title":"Too Many Requests"}"#),
+        ])
+        .await;
+
+        let mut listener = TwitterListener::new(
+            Arc::clone(&ctx),
+            base,
+            "token".into(),
+            "#限界LT",
+            Duration::from_secs(10),
+        );
+
+        assert_eq!(listener.poll().await.unwrap().len(), 2);
+        assert!(listener.poll().await.is_err());
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("GET /2/tweets/search/recent?"));
+        assert!(requests[0].contains("start_time="));
+        assert!(requests[1].contains("since_id=1714528311742505303"));
+    });
+}
+
+#[test]
+fn test_poll_pages() {
+    let ctx = Context::for_test(None);
+
+    ctx.rt.block_on(async {
+        let (base, server) = crate::test_util::mock_http_server(vec![
+            (
+                200,
+                r#"{
+                    "data": [{"author_id": "1234567890", "id": "1714528500000000000", "text": "3本目 #限界LT"}],
+                    "meta": {"newest_id": "1714528500000000000", "next_token": "b26v89c19zqg8o3fpz", "result_count": 1}
+                }"#,
+            ),
+            (200, include_str!("fixtures/twitter_search_recent.json")),
+            (200, r#"{"meta":{"result_count":0}}"#),
+        ])
+        .await;
+
+        let mut listener = TwitterListener::new(
+            Arc::clone(&ctx),
+            base,
+            "token".into(),
+            "#限界LT",
+            Duration::from_secs(10),
+        );
+
+        let texts = listener
+            .poll()
+            .await
+            .unwrap()
+            .iter()
+            .map(|x| x.content.plain_text())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            texts,
+            [
+                "はじまった！ #限界LT",
+                "2本目の発表おもしろい & ためになる #限界LT",
+                "3本目 #限界LT",
+            ]
+        );
+
+        assert!(listener.poll().await.unwrap().is_empty());
+
+        let requests = server.await.unwrap();
+        assert!(requests[1].contains("next_token=b26v89c19zqg8o3fpz"));
+        assert!(requests[1].contains("start_time="));
+
+        // the newest of the first page, not of the last one
+        assert!(requests[2].contains("since_id=1714528500000000000"));
+        assert!(!requests[2].contains("next_token="));
+    });
+}
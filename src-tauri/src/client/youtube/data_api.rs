@@ -0,0 +1,306 @@
+use {
+    super::{color_of_tier, http_client, ChatMessage, ChatPoll, LiveChat, DEFAULT_HIGHLIGHT_COLOR},
+    crate::model::{Content, Highlight},
+    anyhow::{Context as _, Result},
+    async_trait::async_trait,
+    serde::Deserialize,
+    std::time::Duration,
+};
+
+pub(crate) const DEFAULT_BASE_URL: &str = "https://www.googleapis.com/youtube/v3";
+
+#[derive(Deserialize)]
+struct VideoListResponse {
+    #[serde(default)]
+    items: Vec<Video>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Video {
+    live_streaming_details: Option<LiveStreamingDetails>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LiveStreamingDetails {
+    active_live_chat_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LiveChatMessageListResponse {
+    next_page_token: Option<String>,
+    #[serde(default)]
+    polling_interval_millis: u64,
+    #[serde(default)]
+    items: Vec<LiveChatMessage>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LiveChatMessage {
+    snippet: Snippet,
+    author_details: AuthorDetails,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Snippet {
+    #[serde(rename = "type")]
+    kind: String,
+    display_message: Option<String>,
+    super_chat_details: Option<SuperChatDetails>,
+    super_sticker_details: Option<SuperStickerDetails>,
+    member_milestone_chat_details: Option<MemberMilestoneChatDetails>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SuperChatDetails {
+    amount_display_string: String,
+    user_comment: Option<String>,
+    tier: u8,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SuperStickerDetails {
+    amount_display_string: String,
+    tier: u8,
+    super_sticker_metadata: Option<SuperStickerMetadata>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SuperStickerMetadata {
+    alt_text: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MemberMilestoneChatDetails {
+    user_comment: Option<String>,
+    member_month: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthorDetails {
+    display_name: String,
+    profile_image_url: Option<String>,
+}
+
+// the Data API has no emoji images. custom emojis are left as `:shortcut:` in text.
+fn parse_item(item: LiveChatMessage) -> Option<ChatMessage> {
+    let snippet = item.snippet;
+
+    let (text, highlight) = match snippet.kind.as_str() {
+        "textMessageEvent" => (snippet.display_message?, None),
+
+        "superChatEvent" => {
+            let details = snippet.super_chat_details?;
+
+            (
+                details.user_comment.unwrap_or_default(),
+                Some(Highlight::SuperChat {
+                    amount: details.amount_display_string,
+                    color: color_of_tier(details.tier)
+                        .unwrap_or_else(|| DEFAULT_HIGHLIGHT_COLOR.to_string()),
+                    tier: Some(details.tier),
+                }),
+            )
+        }
+
+        "superStickerEvent" => {
+            let details = snippet.super_sticker_details?;
+
+            (
+                details
+                    .super_sticker_metadata
+                    .map(|x| x.alt_text)
+                    .unwrap_or_default(),
+                Some(Highlight::SuperSticker {
+                    amount: details.amount_display_string,
+                    color: color_of_tier(details.tier)
+                        .unwrap_or_else(|| DEFAULT_HIGHLIGHT_COLOR.to_string()),
+                    tier: Some(details.tier),
+                    sticker: None,
+                }),
+            )
+        }
+
+        "newSponsorEvent" => (
+            String::new(),
+            Some(Highlight::Membership {
+                header: "新規メンバー".into(),
+            }),
+        ),
+
+        "memberMilestoneChatEvent" => {
+            let details = snippet.member_milestone_chat_details?;
+
+            (
+                details.user_comment.unwrap_or_default(),
+                Some(Highlight::Membership {
+                    header: format!("メンバー歴 {} か月", details.member_month),
+                }),
+            )
+        }
+
+        _ => return None,
+    };
+
+    Some(ChatMessage {
+        author_name: item.author_details.display_name,
+        author_icon: item.author_details.profile_image_url,
+        content: Content::from_text(&text),
+        highlight,
+    })
+}
+
+fn parse_live_chat_messages(json: &str) -> Result<(Vec<ChatMessage>, Option<String>, Duration)> {
+    let response = serde_json::from_str::<LiveChatMessageListResponse>(json)
+        .context("failed to decode liveChatMessages.list")?;
+
+    let messages = response.items.into_iter().flat_map(parse_item).collect();
+
+    Ok((
+        messages,
+        response.next_page_token,
+        Duration::from_millis(response.polling_interval_millis),
+    ))
+}
+
+/// polls `liveChatMessages.list` of YouTube Data API v3.
+pub(crate) struct DataApiChat {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: String,
+
+    live_chat_id: Option<String>,
+    page_token: Option<String>,
+}
+
+impl DataApiChat {
+    pub(crate) fn new(base_url: String, api_key: String) -> Self {
+        Self {
+            http: http_client(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            live_chat_id: None,
+            page_token: None,
+        }
+    }
+
+    async fn get(&self, path: &str, query: &[(&str, &str)]) -> Result<String> {
+        self.http
+            .get(format!("{}/{}", self.base_url, path))
+            .query(&[("key", self.api_key.as_str())])
+            .query(query)
+            .send()
+            .await
+            .with_context(|| format!("failed to send {}", path))?
+            .error_for_status()
+            .with_context(|| format!("{} failed", path))?
+            .text()
+            .await
+            .with_context(|| format!("failed to read {}", path))
+    }
+
+    async fn list_messages(&self) -> Result<(Vec<ChatMessage>, Option<String>, Duration)> {
+        let live_chat_id = self.live_chat_id.as_deref().context("not connected")?;
+
+        let mut query = vec![
+            ("liveChatId", live_chat_id),
+            ("part", "snippet,authorDetails"),
+        ];
+
+        if let Some(token) = &self.page_token {
+            query.push(("pageToken", token));
+        }
+
+        parse_live_chat_messages(&self.get("liveChat/messages", &query).await?)
+    }
+}
+
+#[async_trait]
+impl LiveChat for DataApiChat {
+    async fn connect(&mut self, video_id: &str) -> Result<()> {
+        let json = self
+            .get(
+                "videos",
+                &[("part", "liveStreamingDetails"), ("id", video_id)],
+            )
+            .await?;
+
+        let live_chat_id = serde_json::from_str::<VideoListResponse>(&json)
+            .context("failed to decode videos.list")?
+            .items
+            .into_iter()
+            .next()
+            .and_then(|x| x.live_streaming_details?.active_live_chat_id)
+            .context("the video has no active live chat")?;
+
+        self.live_chat_id = Some(live_chat_id);
+        self.page_token = None;
+
+        // the first page is the backlog. skip it like the innertube backend does.
+        let (_, page_token, _) = self.list_messages().await?;
+        self.page_token = page_token;
+
+        Ok(())
+    }
+
+    async fn poll(&mut self) -> Result<ChatPoll> {
+        let (messages, page_token, wait) = self.list_messages().await?;
+
+        self.page_token = Some(page_token.context("live chat has ended")?);
+
+        Ok(ChatPoll { messages, wait })
+    }
+}
+
+#[test]
+fn test_parse_live_chat_messages() {
+    let (messages, page_token, wait) =
+        parse_live_chat_messages(include_str!("../fixtures/youtube_live_chat_messages.json"))
+            .unwrap();
+
+    assert_eq!(
+        messages,
+        [
+            ChatMessage {
+                author_name: "視聴者A".into(),
+                author_icon: Some("https://yt3.ggpht.com/a/88.jpg".into()),
+                content: Content::from_text("わこつ"),
+                highlight: None,
+            },
+            ChatMessage {
+                author_name: "視聴者C".into(),
+                author_icon: Some("https://yt3.ggpht.com/c/88.jpg".into()),
+                content: Content::from_text("応援してます"),
+                highlight: Some(Highlight::SuperChat {
+                    amount: "¥1,000".into(),
+                    color: "#00bfa5".into(),
+                    tier: Some(3),
+                }),
+            },
+            // tiers added later are still shown
+            ChatMessage {
+                author_name: "視聴者D".into(),
+                author_icon: Some("https://yt3.ggpht.com/d/88.jpg".into()),
+                content: Content::from_text("がんばれ"),
+                highlight: Some(Highlight::SuperSticker {
+                    amount: "¥100,000".into(),
+                    color: DEFAULT_HIGHLIGHT_COLOR.into(),
+                    tier: Some(9),
+                    sticker: None,
+                }),
+            },
+        ]
+    );
+
+    assert_eq!(page_token.as_deref(), Some("GO3x1tSPuIkDINW65PDc4Ik"));
+    assert_eq!(wait, Duration::from_millis(3000));
+}
@@ -0,0 +1,516 @@
+use {
+    super::{http_client, tier_of_color, ChatMessage, ChatPoll, LiveChat, DEFAULT_HIGHLIGHT_COLOR},
+    crate::model::{Content, Highlight, Segment},
+    anyhow::{Context as _, Result},
+    async_trait::async_trait,
+    once_cell::sync::Lazy,
+    regex::Regex,
+    serde_json::{json, Value},
+    std::{convert::TryFrom, time::Duration},
+};
+
+pub(crate) const DEFAULT_BASE_URL: &str = "https://www.youtube.com";
+
+// used when the page doesn't tell the client version
+const FALLBACK_CLIENT_VERSION: &str = "2.20240101.00.00";
+
+struct Session {
+    api_key: Option<String>,
+    client_version: String,
+    continuation: String,
+}
+
+/// polls `youtubei/v1/live_chat/get_live_chat` with continuation tokens,
+/// which is what the live chat page of youtube.com does.
+pub(crate) struct InnertubeChat {
+    http: reqwest::Client,
+    base_url: String,
+    session: Option<Session>,
+}
+
+impl InnertubeChat {
+    pub(crate) fn new(base_url: String) -> Self {
+        Self {
+            http: http_client(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            session: None,
+        }
+    }
+}
+
+fn capture(regex: &Regex, text: &str) -> Option<String> {
+    Some(regex.captures(text)?.get(1)?.as_str().to_string())
+}
+
+/// extracts what we need to call get_live_chat from the html of `/live_chat?v=...`.
+fn parse_live_chat_page(html: &str) -> Result<Session> {
+    static API_KEY: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#""INNERTUBE_API_KEY"\s*:\s*"([^"]+)""#).unwrap());
+
+    static CLIENT_VERSION: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#""INNERTUBE_CONTEXT_CLIENT_VERSION"\s*:\s*"([^"]+)""#).unwrap());
+
+    static CONTINUATION: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#""continuation"\s*:\s*"([^"]+)""#).unwrap());
+
+    Ok(Session {
+        api_key: capture(&API_KEY, html),
+        client_version: capture(&CLIENT_VERSION, html)
+            .unwrap_or_else(|| FALLBACK_CLIENT_VERSION.to_string()),
+        continuation: capture(&CONTINUATION, html)
+            .context("no continuation in the page. the video may not be live")?,
+    })
+}
+
+/// extracts the video ID of the ongoing live stream from `/@handle/live` or `/channel/UC.../live`.
+/// youtube shows the stream there when live, otherwise the channel top.
+fn parse_live_video_id(html: &str) -> Option<String> {
+    static CANONICAL: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r#"<link rel="canonical" href="https://www\.youtube\.com/watch\?v=([\w-]{11})""#)
+            .unwrap()
+    });
+
+    capture(&CANONICAL, html)
+}
+
+pub(crate) async fn find_live_video(
+    http: &reqwest::Client,
+    base_url: &str,
+    channel: &str,
+) -> Result<String> {
+    let path = if channel.starts_with('@') {
+        format!("{}/live", channel)
+    } else {
+        format!("channel/{}/live", channel)
+    };
+
+    let html = http
+        .get(format!("{}/{}", base_url.trim_end_matches('/'), path))
+        .send()
+        .await
+        .context("failed to fetch live page of the channel")?
+        .error_for_status()
+        .context("failed to fetch live page of the channel")?
+        .text()
+        .await
+        .context("failed to read live page of the channel")?;
+
+    parse_live_video_id(&html).with_context(|| format!("{} is not live now", channel))
+}
+
+// `{"simpleText": "..."}` or `{"runs": [{"text": "..."}, ...]}`
+fn text_of(value: &Value) -> Option<String> {
+    if let Some(text) = value.get("simpleText").and_then(Value::as_str) {
+        return Some(text.to_string());
+    }
+
+    let runs = value.get("runs")?.as_array()?;
+
+    Some(
+        runs.iter()
+            .flat_map(|x| x.get("text").and_then(Value::as_str))
+            .collect(),
+    )
+}
+
+// the largest one
+fn thumbnail_of(value: &Value) -> Option<String> {
+    Some(
+        value
+            .get("thumbnails")?
+            .as_array()?
+            .last()?
+            .get("url")?
+            .as_str()?
+            .to_string(),
+    )
+}
+
+// links in messages go through youtube.com/redirect?q=<actual url>
+fn link_of(run: &Value) -> Option<String> {
+    let url = run
+        .get("navigationEndpoint")?
+        .get("urlEndpoint")?
+        .get("url")?
+        .as_str()?;
+
+    let parsed = reqwest::Url::parse(url).ok()?;
+
+    if parsed.path() == "/redirect" {
+        if let Some((_, q)) = parsed.query_pairs().find(|(k, _)| k == "q") {
+            return Some(q.into_owned());
+        }
+    }
+
+    Some(url.to_string())
+}
+
+fn parse_runs(message: &Value) -> Content {
+    let mut content = Content::new();
+
+    let Some(runs) = message.get("runs").and_then(Value::as_array) else {
+        return content;
+    };
+
+    for run in runs {
+        if let Some(text) = run.get("text").and_then(Value::as_str) {
+            match link_of(run) {
+                Some(url) => content.push(Segment::Link {
+                    url,
+                    text: text.to_string(),
+                }),
+                None => content.push_text(text),
+            }
+
+            continue;
+        }
+
+        let Some(emoji) = run.get("emoji") else {
+            continue;
+        };
+
+        let is_custom = emoji.get("isCustomEmoji").and_then(Value::as_bool) == Some(true);
+
+        let alt = if is_custom {
+            emoji
+                .get("shortcuts")
+                .and_then(|x| x.get(0))
+                .and_then(Value::as_str)
+        } else {
+            emoji.get("emojiId").and_then(Value::as_str)
+        };
+
+        let Some(alt) = alt else {
+            continue;
+        };
+
+        match emoji.get("image").and_then(thumbnail_of) {
+            Some(url) => content.push(Segment::Emoji {
+                url,
+                alt: alt.to_string(),
+            }),
+            None => content.push_text(alt),
+        }
+    }
+
+    content
+}
+
+// the amount matters more than the color. unknown colors are shown in the lowest tier's color
+fn color_of(renderer: &Value, key: &str) -> (String, Option<u8>) {
+    let argb = renderer
+        .get(key)
+        .and_then(Value::as_u64)
+        .and_then(|x| u32::try_from(x).ok());
+
+    match argb {
+        Some(argb) => (format!("#{:06x}", argb & 0xff_ffff), tier_of_color(argb)),
+        None => (DEFAULT_HIGHLIGHT_COLOR.to_string(), None),
+    }
+}
+
+fn parse_chat_item(item: &Value) -> Option<ChatMessage> {
+    let (kind, renderer) = item.as_object()?.iter().next()?;
+
+    let mut content = renderer.get("message").map(parse_runs).unwrap_or_default();
+
+    let highlight = match kind.as_str() {
+        "liveChatTextMessageRenderer" => None,
+
+        "liveChatPaidMessageRenderer" => {
+            let (color, tier) = color_of(renderer, "headerBackgroundColor");
+
+            Some(Highlight::SuperChat {
+                amount: text_of(renderer.get("purchaseAmountText")?)?,
+                color,
+                tier,
+            })
+        }
+
+        "liveChatPaidStickerRenderer" => {
+            let (color, tier) = color_of(renderer, "backgroundColor");
+            let sticker = renderer.get("sticker");
+
+            let label = sticker
+                .and_then(|x| {
+                    x.get("accessibility")?
+                        .get("accessibilityData")?
+                        .get("label")
+                })
+                .and_then(Value::as_str);
+
+            if let Some(label) = label {
+                content.push_text(label);
+            }
+
+            Some(Highlight::SuperSticker {
+                amount: text_of(renderer.get("purchaseAmountText")?)?,
+                color,
+                tier,
+                sticker: sticker.and_then(thumbnail_of),
+            })
+        }
+
+        // milestones have "メンバー歴 3 か月" as primary text, new members have only subtext
+        "liveChatMembershipItemRenderer" => Some(Highlight::Membership {
+            header: renderer
+                .get("headerPrimaryText")
+                .or_else(|| renderer.get("headerSubtext"))
+                .and_then(text_of)
+                .unwrap_or_default(),
+        }),
+
+        _ => return None,
+    };
+
+    Some(ChatMessage {
+        author_name: text_of(renderer.get("authorName")?)?,
+        author_icon: renderer.get("authorPhoto").and_then(thumbnail_of),
+        content,
+        highlight,
+    })
+}
+
+/// returns messages and the next continuation with its timeout.
+/// continuation is None when the chat has ended.
+fn parse_get_live_chat(json: &Value) -> (Vec<ChatMessage>, Option<(String, Duration)>) {
+    let Some(chat) = json
+        .get("continuationContents")
+        .and_then(|x| x.get("liveChatContinuation"))
+    else {
+        return (vec![], None);
+    };
+
+    let messages = chat
+        .get("actions")
+        .and_then(Value::as_array)
+        .map(|x| x.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|x| x.get("addChatItemAction")?.get("item"))
+        .flat_map(parse_chat_item)
+        .collect();
+
+    let next = chat
+        .get("continuations")
+        .and_then(Value::as_array)
+        .and_then(|x| x.first())
+        .and_then(|x| {
+            x.get("invalidationContinuationData")
+                .or_else(|| x.get("timedContinuationData"))
+                .or_else(|| x.get("reloadContinuationData"))
+        })
+        .and_then(|x| {
+            let continuation = x.get("continuation")?.as_str()?.to_string();
+            let timeout = x.get("timeoutMs").and_then(Value::as_u64).unwrap_or(0);
+
+            Some((continuation, Duration::from_millis(timeout)))
+        });
+
+    (messages, next)
+}
+
+#[async_trait]
+impl LiveChat for InnertubeChat {
+    async fn connect(&mut self, video_id: &str) -> Result<()> {
+        let html = self
+            .http
+            .get(format!("{}/live_chat", self.base_url))
+            .query(&[("v", video_id)])
+            .send()
+            .await
+            .context("failed to fetch live chat page")?
+            .error_for_status()
+            .context("failed to fetch live chat page")?
+            .text()
+            .await
+            .context("failed to read live chat page")?;
+
+        self.session = Some(parse_live_chat_page(&html)?);
+
+        Ok(())
+    }
+
+    async fn poll(&mut self) -> Result<ChatPoll> {
+        let session = self.session.as_mut().context("not connected")?;
+
+        let mut request = self
+            .http
+            .post(format!(
+                "{}/youtubei/v1/live_chat/get_live_chat",
+                self.base_url
+            ))
+            .query(&[("prettyPrint", "false")]);
+
+        if let Some(key) = &session.api_key {
+            request = request.query(&[("key", key)]);
+        }
+
+        let json = request
+            .json(&json!({
+                "context": {
+                    "client": {
+                        "clientName": "WEB",
+                        "clientVersion": session.client_version,
+                    }
+                },
+                "continuation": session.continuation,
+            }))
+            .send()
+            .await
+            .context("failed to send get_live_chat")?
+            .error_for_status()
+            .context("get_live_chat failed")?
+            .json::<Value>()
+            .await
+            .context("failed to decode get_live_chat")?;
+
+        let (messages, next) = parse_get_live_chat(&json);
+
+        let (continuation, wait) = next.context("live chat has ended")?;
+        session.continuation = continuation;
+
+        Ok(ChatPoll { messages, wait })
+    }
+}
+
+#[test]
+fn test_parse_live_chat_page() {
+    let session =
+        parse_live_chat_page(include_str!("../fixtures/youtube_live_chat_page.html")).unwrap();
+
+    assert_eq!(session.api_key.as_deref(), Some("AIzaSyDUMMY_KEY"));
+    assert_eq!(session.client_version, "2.20241017.01.00");
+    assert_eq!(
+        session.continuation,
+        "0ofMyANhGlhDaWtxSndvWVZVTXRaMlZ1YTJGcA"
+    );
+
+    assert!(parse_live_chat_page("<html></html>").is_err());
+}
+
+#[test]
+fn test_parse_live_video_id() {
+    let live = r#"<head><link rel="canonical" href="https://www.youtube.com/watch?v=5VoIGGMYrDg"><meta name="title"></head>"#;
+    assert_eq!(parse_live_video_id(live).as_deref(), Some("5VoIGGMYrDg"));
+
+    let offline = r#"<head><link rel="canonical" href="https://www.youtube.com/channel/UC-9-kyTW8ZkZNDHQJ6FgpwQ"></head>"#;
+    assert_eq!(parse_live_video_id(offline), None);
+}
+
+#[test]
+fn test_parse_get_live_chat() {
+    let json =
+        serde_json::from_str(include_str!("../fixtures/youtube_get_live_chat.json")).unwrap();
+
+    let (messages, next) = parse_get_live_chat(&json);
+
+    assert_eq!(
+        messages,
+        [
+            ChatMessage {
+                author_name: "視聴者A".into(),
+                author_icon: Some("https://yt4.ggpht.com/a/64.jpg".into()),
+                content: {
+                    let mut content = Content::new();
+
+                    content.push(Segment::Emoji {
+                        url: "https://yt3.ggpht.com/emoji/wave.png".into(),
+                        alt: ":wave:".into(),
+                    });
+                    content.push_text(" わこつ ");
+                    content.push(Segment::Link {
+                        url: "https://example.com/long/path".into(),
+                        text: "https://example.com/...".into(),
+                    });
+                    content.push_text(" みてね");
+                    content.push(Segment::Emoji {
+                        url: "https://fonts.gstatic.com/s/e/notoemoji/1f44f.png".into(),
+                        alt: "👏".into(),
+                    });
+
+                    content
+                },
+                highlight: None,
+            },
+            ChatMessage {
+                author_name: "視聴者B".into(),
+                author_icon: None,
+                content: Content::from_text("8888"),
+                highlight: None,
+            },
+            ChatMessage {
+                author_name: "視聴者C".into(),
+                author_icon: None,
+                content: Content::from_text("応援してます"),
+                highlight: Some(Highlight::SuperChat {
+                    amount: "¥1,000".into(),
+                    color: "#00bfa5".into(),
+                    tier: Some(3),
+                }),
+            },
+            ChatMessage {
+                author_name: "視聴者D".into(),
+                author_icon: None,
+                content: Content::from_text("拍手するねこ"),
+                highlight: Some(Highlight::SuperSticker {
+                    amount: "¥200".into(),
+                    color: "#1565c0".into(),
+                    tier: Some(1),
+                    sticker: Some("https://lh3.googleusercontent.com/sticker=s80".into()),
+                }),
+            },
+            ChatMessage {
+                author_name: "視聴者E".into(),
+                author_icon: None,
+                content: Content::from_text("いつもありがとう"),
+                highlight: Some(Highlight::Membership {
+                    header: "メンバー歴 3 か月".into(),
+                }),
+            },
+            ChatMessage {
+                author_name: "視聴者F".into(),
+                author_icon: None,
+                content: Content::from_text("色なし"),
+                highlight: Some(Highlight::SuperChat {
+                    amount: "$5.00".into(),
+                    color: "#1565c0".into(),
+                    tier: None,
+                }),
+            },
+        ]
+    );
+
+    assert_eq!(
+        next,
+        Some((
+            "0ofMyANhGlhDaWtxSndvWVZVTXRaMlZ1YTJGcE5ESXhOV0Zv".into(),
+            Duration::from_millis(5000)
+        ))
+    );
+
+    assert_eq!(parse_get_live_chat(&json!({})), (vec![], None));
+}
+
+#[tokio::test]
+async fn test_poll() {
+    let (base, server) = crate::test_util::mock_http_server(vec![
+        (200, include_str!("../fixtures/youtube_live_chat_page.html")),
+        (200, include_str!("../fixtures/youtube_get_live_chat.json")),
+    ])
+    .await;
+
+    let mut chat = InnertubeChat::new(base);
+    chat.connect("VIDEO_ID").await.unwrap();
+
+    let poll = chat.poll().await.unwrap();
+    assert_eq!(poll.messages.len(), 6);
+    assert_eq!(poll.wait, Duration::from_millis(5000));
+
+    let requests = server.await.unwrap();
+    assert!(requests[0].starts_with("GET /live_chat?v=VIDEO_ID "));
+    assert!(requests[1].starts_with(
+        "POST /youtubei/v1/live_chat/get_live_chat?prettyPrint=false&key=AIzaSyDUMMY_KEY "
+    ));
+    assert!(requests[1].contains(r#""continuation":"0ofMyANhGlhDaWtxSndvWVZVTXRaMlZ1YTJGcA""#));
+}
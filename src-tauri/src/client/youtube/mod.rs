@@ -0,0 +1,348 @@
+mod data_api;
+mod innertube;
+
+use {
+    crate::{
+        model::{Content, Highlight, Service, User},
+        moderation::{self, Incoming},
+        Context,
+    },
+    anyhow::{bail, Error, Result},
+    async_trait::async_trait,
+    once_cell::sync::Lazy,
+    regex::Regex,
+    std::{fmt, sync::Arc, time::Duration},
+    tokio::{sync::watch, time::sleep},
+};
+
+pub(crate) use {
+    data_api::{DataApiChat, DEFAULT_BASE_URL as DATA_API_BASE_URL},
+    innertube::{InnertubeChat, DEFAULT_BASE_URL as INNERTUBE_BASE_URL},
+};
+
+const MIN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+// without a browser-like UA youtube serves a page without live chat
+const USER_AGENT: &str =
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36";
+
+// header colors of super chat tiers in ascending order
+const SUPER_CHAT_COLORS: [u32; 7] = [
+    0x1565c0, 0x00b8d4, 0x00bfa5, 0xffb300, 0xe65100, 0xc2185b, 0xd00000,
+];
+
+const DEFAULT_HIGHLIGHT_COLOR: &str = "#1565c0";
+
+fn tier_of_color(argb: u32) -> Option<u8> {
+    let position = SUPER_CHAT_COLORS
+        .iter()
+        .position(|&x| x == argb & 0xff_ffff)?;
+
+    Some(position as u8 + 1)
+}
+
+fn color_of_tier(tier: u8) -> Option<String> {
+    let rgb = SUPER_CHAT_COLORS.get(usize::from(tier).checked_sub(1)?)?;
+    Some(format!("#{:06x}", rgb))
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct ChatMessage {
+    author_name: String,
+    author_icon: Option<String>,
+    content: Content,
+    highlight: Option<Highlight>,
+}
+
+pub(crate) struct ChatPoll {
+    messages: Vec<ChatMessage>,
+
+    // how long the server wants us to wait before the next poll
+    wait: Duration,
+}
+
+/// a way to read the live chat of a video.
+#[async_trait]
+pub(crate) trait LiveChat: Send + Sync {
+    /// prepares polling of the chat of `video_id`. messages before this are not returned.
+    async fn connect(&mut self, video_id: &str) -> Result<()>;
+
+    async fn poll(&mut self) -> Result<ChatPoll>;
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum YoutubeBackend {
+    /// the same internal API as youtube.com uses. needs no API key.
+    Innertube,
+
+    /// official `liveChatMessages.list` of YouTube Data API v3. consumes quota.
+    DataApi,
+}
+
+impl YoutubeBackend {
+    pub(crate) fn parse(s: &str) -> Result<Self> {
+        match s {
+            "innertube" => Ok(Self::Innertube),
+            "data_api" => Ok(Self::DataApi),
+            _ => bail!("unknown youtube backend {}. use innertube or data_api", s),
+        }
+    }
+}
+
+/// what to listen. a channel is resolved to its current live stream on every (re)connection,
+/// so a restarted stream with a new video ID is followed automatically.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum YoutubeTarget {
+    Video(String),
+
+    /// `@handle` or channel ID (`UC...`)
+    Channel(String),
+}
+
+impl YoutubeTarget {
+    /// accepts a video ID, a URL of a video or a channel, `@handle` or a channel ID.
+    pub(crate) fn parse(s: &str) -> Result<Self> {
+        static VIDEO_URL: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(r"(?:[?&]v=|youtu\.be/|/live/|/shorts/)([\w-]{11})(?:[^\w-]|$)").unwrap()
+        });
+
+        static CHANNEL_URL: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"youtube\.com/(?:channel/)?(@[\w.-]+|UC[\w-]{22})").unwrap());
+
+        static VIDEO_ID: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[\w-]{11}$").unwrap());
+        static CHANNEL_ID: Lazy<Regex> = Lazy::new(|| Regex::new(r"^UC[\w-]{22}$").unwrap());
+
+        let s = s.trim().trim_start_matches('<').trim_end_matches('>');
+
+        if let Some(c) = VIDEO_URL.captures(s) {
+            return Ok(Self::Video(c[1].to_string()));
+        }
+
+        if let Some(c) = CHANNEL_URL.captures(s) {
+            return Ok(Self::Channel(c[1].to_string()));
+        }
+
+        if s.starts_with('@') || CHANNEL_ID.is_match(s) {
+            return Ok(Self::Channel(s.to_string()));
+        }
+
+        if VIDEO_ID.is_match(s) {
+            return Ok(Self::Video(s.to_string()));
+        }
+
+        bail!(
+            "{} is neither a video ID, a URL, @handle nor a channel ID",
+            s
+        )
+    }
+}
+
+impl fmt::Display for YoutubeTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Video(id) => write!(f, "video {}", id),
+            Self::Channel(channel) => write!(f, "the live stream of {}", channel),
+        }
+    }
+}
+
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .expect("failed to build http client")
+}
+
+fn backoff(retry: u32) -> Duration {
+    MIN_POLL_INTERVAL
+        .checked_mul(2u32.saturating_pow(retry))
+        .map_or(MAX_BACKOFF, |x| x.min(MAX_BACKOFF))
+}
+
+/// reads the live chat of the target and pushes messages to the timeline.
+/// the target can be replaced at any time through the `watch` channel.
+pub(crate) struct YoutubeListener {
+    ctx: Arc<Context>,
+    http: reqwest::Client,
+    chat: Box<dyn LiveChat>,
+    target: watch::Receiver<Option<YoutubeTarget>>,
+}
+
+impl YoutubeListener {
+    pub(crate) fn new(
+        ctx: Arc<Context>,
+        chat: Box<dyn LiveChat>,
+        target: watch::Receiver<Option<YoutubeTarget>>,
+    ) -> Self {
+        Self {
+            ctx,
+            http: http_client(),
+            chat,
+            target,
+        }
+    }
+
+    pub(crate) async fn start(self) {
+        let Self {
+            ctx,
+            http,
+            mut chat,
+            mut target,
+        } = self;
+
+        let mut retry = 0;
+
+        loop {
+            let current = target.borrow_and_update().clone();
+
+            let Some(current) = current else {
+                tracing::info!("youtube live chat is not configured. waiting for a target");
+
+                if target.changed().await.is_err() {
+                    return;
+                }
+
+                continue;
+            };
+
+            tokio::select! {
+                (e, polled) = run(&ctx, &http, chat.as_mut(), &current) => {
+                    // transient errors after a while of polling start over from the shortest wait
+                    if polled {
+                        retry = 0;
+                    }
+
+                    let wait = backoff(retry);
+                    retry += 1;
+
+                    tracing::error!(
+                        "youtube live chat of {} stopped. reconnecting in {:?}: {:?}",
+                        current,
+                        wait,
+                        e
+                    );
+
+                    tokio::select! {
+                        _ = sleep(wait) => {}
+                        _ = target.changed() => retry = 0,
+                    }
+                }
+
+                changed = target.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+
+                    retry = 0;
+                }
+            }
+        }
+    }
+}
+
+// returns only on errors, with whether any poll succeeded before
+async fn run(
+    ctx: &Context,
+    http: &reqwest::Client,
+    chat: &mut dyn LiveChat,
+    target: &YoutubeTarget,
+) -> (Error, bool) {
+    let mut polled = false;
+
+    let result: Result<()> = async {
+        let video_id = match target {
+            YoutubeTarget::Video(id) => id.clone(),
+            YoutubeTarget::Channel(channel) => {
+                innertube::find_live_video(http, innertube::DEFAULT_BASE_URL, channel).await?
+            }
+        };
+
+        chat.connect(&video_id).await?;
+
+        tracing::info!("listening youtube live chat of {} ({})", video_id, target);
+
+        loop {
+            let poll = chat.poll().await?;
+            polled = true;
+
+            for message in poll.messages {
+                push(ctx, message).await;
+            }
+
+            sleep(poll.wait.max(MIN_POLL_INTERVAL)).await;
+        }
+    }
+    .await;
+
+    match result {
+        Ok(()) => unreachable!(),
+        Err(e) => (e, polled),
+    }
+}
+
+async fn push(ctx: &Context, message: ChatMessage) {
+    moderation::submit(
+        ctx,
+        Incoming {
+            service: Service::Youtube,
+            // channel IDs are not available in every backend. names are what operators see anyway.
+            author: message.author_name.clone(),
+            source_id: None,
+            user: User {
+                icon: message.author_icon,
+                ident: None,
+                name: message.author_name,
+            },
+            content: message.content,
+            highlight: message.highlight,
+        },
+    )
+    .await;
+}
+
+#[test]
+fn test_tier() {
+    assert_eq!(tier_of_color(0xff1565c0), Some(1));
+    assert_eq!(tier_of_color(0xffd00000), Some(7));
+    assert_eq!(tier_of_color(0xff123456), None);
+
+    assert_eq!(color_of_tier(4).as_deref(), Some("#ffb300"));
+    assert_eq!(color_of_tier(0), None);
+    assert_eq!(color_of_tier(8), None);
+}
+
+#[test]
+fn test_parse_target() {
+    use YoutubeTarget::*;
+
+    let parse = |s| YoutubeTarget::parse(s).unwrap();
+    let video = || Video("5VoIGGMYrDg".into());
+
+    assert_eq!(parse("5VoIGGMYrDg"), video());
+    assert_eq!(
+        parse("https://www.youtube.com/watch?v=5VoIGGMYrDg"),
+        video()
+    );
+    assert_eq!(
+        parse("https://www.youtube.com/watch?feature=share&v=5VoIGGMYrDg"),
+        video()
+    );
+    assert_eq!(parse("<https://youtu.be/5VoIGGMYrDg?si=abc>"), video());
+    assert_eq!(parse("https://www.youtube.com/live/5VoIGGMYrDg"), video());
+
+    assert_eq!(parse("@approvers"), Channel("@approvers".into()));
+    assert_eq!(
+        parse("https://www.youtube.com/@approvers/streams"),
+        Channel("@approvers".into())
+    );
+
+    let channel_id = "UC-9-kyTW8ZkZNDHQJ6FgpwQ";
+    assert_eq!(parse(channel_id), Channel(channel_id.into()));
+    assert_eq!(
+        parse(&format!("https://www.youtube.com/channel/{}", channel_id)),
+        Channel(channel_id.into())
+    );
+
+    assert!(YoutubeTarget::parse("hello").is_err());
+}
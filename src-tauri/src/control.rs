@@ -0,0 +1,1021 @@
+use {
+    crate::{
+        model::{Page, ScreenAction},
+        presentations::Presentation,
+        social,
+        timer::PresentationTimer,
+        Context,
+    },
+    serde::Deserialize,
+    std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::sync::mpsc::Sender,
+};
+
+#[cfg(feature = "obs")]
+use crate::obs::ObsAction;
+
+/// operations on the screen which don't depend on how they were requested.
+/// Discord commands and the local control server are translated into this.
+#[derive(Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub(crate) enum ControlCommand {
+    SetNotification {
+        text: String,
+    },
+    TimelineClear,
+    /// lists the latest items on the timeline with the IDs which `TimelineRemove` takes.
+    TimelineList,
+    TimelineRemove {
+        id: u64,
+    },
+    /// hides messages from the user and removes the ones already shown.
+    /// `user` is "<service>:<author>", e.g. "discord:1234" or "youtube:name".
+    MuteUser {
+        user: String,
+        /// muted until unmuted if omitted
+        #[serde(default)]
+        duration_secs: Option<u64>,
+    },
+    UnmuteUser {
+        user: String,
+    },
+    /// switches the YouTube live chat to listen. accepts what `YoutubeTarget::parse` does.
+    Youtube {
+        target: String,
+    },
+    Pause,
+    Resume,
+    /// describes the screen, the queue and the OBS connection.
+    Status,
+    Presentation(PresentationCommand),
+    Timer(TimerCommand),
+    /// streaming, recording and so on in OBS
+    Obs(ObsCommand),
+    PresentationTweet {
+        #[serde(default)]
+        simulation: bool,
+    },
+    Tweet {
+        #[serde(default)]
+        with_youtube_footer: bool,
+        #[serde(default)]
+        with_discord_footer: bool,
+        #[serde(default)]
+        with_twitter_footer: bool,
+        msg: String,
+        #[serde(default)]
+        simulation: bool,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub(crate) enum PresentationCommand {
+    Push(Presentation),
+    Reorder {
+        map: Vec<usize>,
+    },
+    Remove {
+        index: usize,
+    },
+    Update {
+        index: usize,
+        new_title: String,
+    },
+    List,
+    Pop,
+    /// puts the current presentation back to the head of the queue.
+    /// the previous one becomes current again. used to undo `Pop`,
+    /// so it can't be sent from the outside.
+    #[serde(skip_deserializing)]
+    Unpop {
+        /// of the previous presentation, which is given back
+        timer: Option<PresentationTimer>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub(crate) enum TimerCommand {
+    Pause,
+    Resume,
+    Extend { secs: u64 },
+    Reset,
+    Stop,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub(crate) enum ObsCommand {
+    StreamStart,
+    StreamStop {
+        #[serde(default)]
+        confirmed: bool,
+    },
+    RecordStart,
+    RecordStop {
+        #[serde(default)]
+        confirmed: bool,
+    },
+    RecordPause,
+    RecordResume,
+    /// saves the replay buffer
+    ReplaySave,
+    /// saves a screenshot of the scene as a file
+    Screenshot {
+        scene: String,
+    },
+}
+
+impl ObsCommand {
+    /// what can't be taken back unless confirmed, described for the confirmation
+    pub(crate) fn needs_confirmation(&self) -> Option<&'static str> {
+        match self {
+            Self::StreamStop { confirmed: false } => Some("this stops the stream"),
+            Self::RecordStop { confirmed: false } => Some("this stops the recording"),
+            _ => None,
+        }
+    }
+}
+
+// how long to wait for OBS to carry out a command
+#[cfg(feature = "obs")]
+const OBS_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+// how many commands can be undone in a row
+const UNDO_LIMIT: usize = 20;
+
+// items listed by `TimelineList`
+const TIMELINE_LIST_LIMIT: usize = 15;
+
+/// records the command which reverts what was just done, for `undo`.
+async fn remember(ctx: &Context, inverse: ControlCommand) {
+    let mut stack = ctx.undo_stack.write().await;
+
+    if stack.len() >= UNDO_LIMIT {
+        stack.remove(0);
+    }
+
+    stack.push(inverse);
+}
+
+/// reverts the last undoable command, i.e. pause, resume, set_notification, mute_user,
+/// presentation pop, and timer pause/resume.
+pub(crate) async fn undo(ctx: &Arc<Context>) -> String {
+    let Some(inverse) = ctx.undo_stack.write().await.pop() else {
+        return "nothing to undo".into();
+    };
+
+    let len = ctx.undo_stack.read().await.len();
+    let result = execute(ctx, inverse).await;
+
+    // undoing is not undoable itself
+    ctx.undo_stack.write().await.truncate(len);
+
+    format!("undone: {}", result)
+}
+
+#[cfg(feature = "obs")]
+async fn send_obs(ctx: &Context, action: ObsAction) {
+    match ctx.obs_chan.read().await.as_ref() {
+        Some(obs_chan) => {
+            obs_chan.send(action).await.ok();
+        }
+
+        None => {
+            tracing::warn!(
+                "failed to send {:?} to obs because obs_channel was not initialized",
+                action
+            );
+        }
+    }
+}
+
+#[cfg(feature = "obs")]
+async fn obs_command(ctx: &Context, command: ObsCommand) -> String {
+    let (reply, result) = tokio::sync::oneshot::channel();
+    send_obs(ctx, ObsAction::Command { command, reply }).await;
+
+    match tokio::time::timeout(OBS_COMMAND_TIMEOUT, result).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => "obs is not running".into(),
+        Err(_) => "obs didn't respond in time. check obs directly".into(),
+    }
+}
+
+async fn update_presentations(ctx: &Arc<Context>, sender: &Sender<ScreenAction>) {
+    sender
+        .send(ScreenAction::UpcomingPresentationsUpdate(Arc::clone(ctx)))
+        .await
+        .ok();
+}
+
+/// runs the command and returns a human readable result.
+pub(crate) async fn execute(ctx: &Arc<Context>, cmd: ControlCommand) -> String {
+    let result = execute_inner(ctx, cmd).await;
+
+    // most of commands change something worth to be restored after a crash
+    ctx.state_file.request_save();
+
+    result
+}
+
+async fn execute_inner(ctx: &Arc<Context>, cmd: ControlCommand) -> String {
+    use ControlCommand::*;
+    use PresentationCommand::*;
+
+    match (cmd, ctx.webview_chan.read().await.as_ref()) {
+        (SetNotification { text }, Some(sender)) => {
+            // held until sent so that the next one sees this as the previous
+            let mut last = ctx.notification.write().await;
+
+            // undoing the first one clears the notification
+            let previous = std::mem::replace(&mut *last, text.clone());
+            remember(ctx, SetNotification { text: previous }).await;
+
+            sender
+                .send(ScreenAction::NotificationUpdate { text })
+                .await
+                .ok();
+
+            "set".into()
+        }
+
+        (TimelineClear, Some(sender)) => {
+            sender.send(ScreenAction::TimelineClear).await.ok();
+            "cleared".into()
+        }
+
+        (TimelineList, _) => {
+            let items = ctx
+                .screen_state
+                .read()
+                .await
+                .recent_items(TIMELINE_LIST_LIMIT);
+
+            if items.is_empty() {
+                return "timeline is empty".into();
+            }
+
+            format!("```\n{}\n```", items.join("\n"))
+        }
+
+        (TimelineRemove { id }, Some(sender)) => {
+            sender.send(ScreenAction::TimelineRemove { id }).await.ok();
+            "removed".into()
+        }
+
+        (
+            MuteUser {
+                user,
+                duration_secs,
+            },
+            Some(sender),
+        ) => {
+            ctx.moderator
+                .mute(user.clone(), duration_secs.map(Duration::from_secs));
+
+            sender
+                .send(ScreenAction::TimelineRemoveUser {
+                    author: user.clone(),
+                })
+                .await
+                .ok();
+
+            tracing::info!("muted {} for {:?} secs", user, duration_secs);
+            remember(ctx, UnmuteUser { user: user.clone() }).await;
+
+            match duration_secs {
+                Some(secs) => format!("muted {} for {} secs", user, secs),
+                None => format!("muted {} until unmuted", user),
+            }
+        }
+
+        (UnmuteUser { user }, _) => {
+            if ctx.moderator.unmute(&user) {
+                format!("unmuted {}", user)
+            } else {
+                format!("{} is not muted", user)
+            }
+        }
+
+        // TODO: lock during switching (2sec)
+        (Pause, Some(sender)) => {
+            remember(ctx, Resume).await;
+
+            sender
+                .send(ScreenAction::SwitchPage(Page::WaitingScreen))
+                .await
+                .ok();
+
+            #[cfg(feature = "obs")]
+            {
+                send_obs(ctx, ObsAction::ShowPage(Page::WaitingScreen)).await;
+                send_obs(ctx, ObsAction::Mute).await;
+            }
+
+            "switching requested".into()
+        }
+
+        (Resume, Some(sender)) => {
+            remember(ctx, Pause).await;
+
+            sender
+                .send(ScreenAction::SwitchPage(Page::LTScreen))
+                .await
+                .ok();
+
+            #[cfg(feature = "obs")]
+            {
+                send_obs(ctx, ObsAction::ShowPage(Page::LTScreen)).await;
+                send_obs(ctx, ObsAction::UnMute).await;
+            }
+
+            "switching requested".into()
+        }
+
+        (Status, _) => status(ctx).await,
+
+        (Obs(command), _) => {
+            if let Some(warning) = command.needs_confirmation() {
+                return format!("{}. confirm to proceed", warning);
+            }
+
+            #[cfg(feature = "obs")]
+            {
+                obs_command(ctx, command).await
+            }
+
+            #[cfg(not(feature = "obs"))]
+            {
+                let _ = command;
+                "obs feature is not enabled".into()
+            }
+        }
+
+        (Presentation(List), _) => {
+            let mut list = ctx.presentations.read().await.list();
+
+            // make list codeblock
+            list.insert_str(0, "```\n");
+            list.push_str("\n```");
+
+            list
+        }
+
+        (Presentation(Pop), Some(sender)) => {
+            let Some(popped) = ctx.presentations.write().await.pop() else {
+                return "no other entries in queue".into();
+            };
+
+            update_presentations(ctx, sender).await;
+
+            sender
+                .send(ScreenAction::PresentationUpdate {
+                    presenter: popped.presenter.clone(),
+                    title: popped.title.clone(),
+                })
+                .await
+                .ok();
+
+            let previous_timer = ctx.timer.write().await.replace(PresentationTimer::start(
+                popped.duration(ctx.default_presentation_duration),
+                Instant::now(),
+            ));
+
+            #[cfg(feature = "obs")]
+            let talk = ObsAction::TalkStarted {
+                presenter: popped.presenter.name.clone(),
+                title: popped.title.clone(),
+            };
+
+            let finished = ctx.current_presentation.write().await.replace(popped);
+
+            if let Some(finished) = finished {
+                ctx.finished_presentations.write().await.push(finished);
+            }
+
+            remember(
+                ctx,
+                Presentation(Unpop {
+                    timer: previous_timer,
+                }),
+            )
+            .await;
+
+            #[cfg(feature = "obs")]
+            {
+                send_obs(ctx, ObsAction::PresenterIntro).await;
+                send_obs(ctx, talk).await;
+            }
+
+            // TODO: introduce command
+            "popped(removed an entry at 0 index and updated ongoing presentation)\nDO NOT FORGET TO TWEET!".into()
+        }
+
+        (Presentation(Unpop { timer }), Some(sender)) => {
+            let Some(current) = ctx.current_presentation.write().await.take() else {
+                return "no presentation is ongoing".into();
+            };
+
+            #[cfg(feature = "obs")]
+            let cancelled = ObsAction::TalkCancelled {
+                presenter: current.presenter.name.clone(),
+                title: current.title.clone(),
+            };
+
+            ctx.presentations.write().await.push_front(current);
+
+            let previous = ctx.finished_presentations.write().await.pop();
+
+            let action = match &previous {
+                Some(p) => ScreenAction::PresentationUpdate {
+                    presenter: p.presenter.clone(),
+                    title: p.title.clone(),
+                },
+
+                None => ScreenAction::PresentationClear,
+            };
+
+            sender.send(action).await.ok();
+
+            *ctx.current_presentation.write().await = previous;
+
+            // the previous timer has been going on as if it was never popped
+            let mut lock = ctx.timer.write().await;
+            let undone = std::mem::replace(&mut *lock, timer);
+
+            match lock.as_ref() {
+                Some(timer) => {
+                    sender.send(timer.to_action(Instant::now())).await.ok();
+                }
+
+                None if undone.is_some() => {
+                    sender.send(ScreenAction::TimerClear).await.ok();
+                }
+
+                None => {}
+            }
+
+            drop(lock);
+
+            update_presentations(ctx, sender).await;
+
+            #[cfg(feature = "obs")]
+            send_obs(ctx, cancelled).await;
+
+            "put the current presentation back to the queue".into()
+        }
+
+        (Presentation(Reorder { map }), Some(sender)) => {
+            let mut lock = ctx.presentations.write().await;
+
+            if let Err(e) = lock.reorder(&map) {
+                return format!("couldn't reorder: {}", e);
+            }
+
+            let mut list = lock.list();
+            drop(lock);
+
+            update_presentations(ctx, sender).await;
+
+            list.insert_str(0, "reordered\n```\n");
+            list.push_str("\n```");
+
+            list
+        }
+
+        (Presentation(Remove { index }), Some(sender)) => {
+            let deleted = ctx.presentations.write().await.remove(index);
+
+            if deleted {
+                update_presentations(ctx, sender).await;
+                "removed"
+            } else {
+                "not found such entry"
+            }
+            .into()
+        }
+
+        (Presentation(Update { index, new_title }), Some(sender)) => {
+            let mut lock = ctx.presentations.write().await;
+            let present = lock.get_mut(index);
+
+            match present {
+                Some(p) => {
+                    p.title = new_title;
+                    update_presentations(ctx, sender).await;
+                    "overwrote".into()
+                }
+
+                None => "not found such entry".into(),
+            }
+        }
+
+        (Presentation(Push(presentation)), Some(sender)) => {
+            ctx.presentations.write().await.push(presentation);
+
+            update_presentations(ctx, sender).await;
+
+            "pushed".into()
+        }
+
+        (Timer(TimerCommand::Stop), Some(sender)) => {
+            if ctx.timer.write().await.take().is_none() {
+                return "timer is not running".into();
+            }
+
+            sender.send(ScreenAction::TimerClear).await.ok();
+
+            "stopped".into()
+        }
+
+        (Timer(cmd), Some(sender)) => {
+            let mut lock = ctx.timer.write().await;
+
+            let Some(timer) = lock.as_mut() else {
+                return "timer is not running. pop a presentation first".into();
+            };
+
+            let now = Instant::now();
+
+            let text = match cmd {
+                TimerCommand::Pause => {
+                    if timer.pause(now) {
+                        remember(ctx, Timer(TimerCommand::Resume)).await;
+                        "paused"
+                    } else {
+                        "already paused"
+                    }
+                }
+
+                TimerCommand::Resume => {
+                    if timer.resume(now) {
+                        remember(ctx, Timer(TimerCommand::Pause)).await;
+                        "resumed"
+                    } else {
+                        "already running"
+                    }
+                }
+
+                TimerCommand::Extend { secs } => {
+                    timer.extend(Duration::from_secs(secs));
+                    "extended"
+                }
+
+                TimerCommand::Reset => {
+                    timer.reset(now);
+                    "reset"
+                }
+
+                TimerCommand::Stop => unreachable!(),
+            };
+
+            sender.send(timer.to_action(now)).await.ok();
+
+            text.into()
+        }
+
+        (
+            Tweet {
+                with_youtube_footer,
+                with_discord_footer,
+                with_twitter_footer,
+                msg,
+                simulation,
+            },
+            _,
+        ) => {
+            let mut message = msg;
+
+            let has_footer = with_youtube_footer || with_discord_footer || with_twitter_footer;
+
+            if has_footer {
+                message.push('\n');
+            }
+
+            if with_youtube_footer {
+                message.push('\n');
+
+                message.push_str(&format!(
+                    include_str!("tweet_template/footer/youtube.fmt.txt"),
+                    YOUTUBE_URL = ctx.sns_info.youtube_stream_url
+                ));
+            }
+
+            if with_discord_footer {
+                message.push('\n');
+
+                message.push_str(&format!(
+                    include_str!("tweet_template/footer/discord.fmt.txt"),
+                    DISCORD_INVITATION_URL = ctx.sns_info.discord_invitation_url
+                ));
+            }
+
+            if with_twitter_footer {
+                message.push('\n');
+                message.push_str(include_str!("tweet_template/footer/twitter.txt"));
+            }
+
+            let violations = social::check_length(&ctx.social_posters, &message);
+
+            if !violations.is_empty() {
+                return format!(
+                    "Message is too long. Shorten the message or the footer.\n{}",
+                    violations.join("\n")
+                );
+            }
+
+            if !simulation {
+                let links = post(ctx, &message).await;
+                message = format!("Posted.\n{}\nbody:\n```\n{}\n```", links, message);
+            } else {
+                message = format!("Tweet simulation.\nbody:\n```\n{}\n```", message);
+            };
+
+            message
+        }
+
+        (PresentationTweet { simulation }, _) => {
+            let msg = match ctx.current_presentation.read().await.as_ref() {
+                Some(pre) => {
+                    format!(
+                        include_str!("tweet_template/begin_presentation.fmt.txt"),
+                        TITLE = pre.title,
+                        PRESENTER_NAME = pre.presenter.name,
+                        RANDOM_FOOTER = random_footer(ctx)
+                    )
+                }
+
+                None => {
+                    return "internal error: current_presentation was None".into();
+                }
+            };
+
+            if simulation {
+                return format!("Simulation.\nbody: ```\n{}\n```", msg);
+            }
+
+            let violations = social::check_length(&ctx.social_posters, &msg);
+
+            if !violations.is_empty() {
+                return format!(
+                    "Message is too long. Fix the template.\n{}",
+                    violations.join("\n")
+                );
+            }
+
+            let links = post(ctx, &msg).await;
+
+            format!("Posted.\n{}\nbody: ```\n{}\n```", links, msg)
+        }
+
+        (Youtube { target }, _) => {
+            #[cfg(feature = "youtube")]
+            {
+                match crate::client::youtube::YoutubeTarget::parse(&target) {
+                    Ok(target) => {
+                        let text = format!("now listening youtube live chat of {}", target);
+                        ctx.youtube_target.send_replace(Some(target));
+                        text
+                    }
+
+                    Err(e) => e.to_string(),
+                }
+            }
+
+            #[cfg(not(feature = "youtube"))]
+            {
+                let _ = target;
+                "youtube feature is not enabled".into()
+            }
+        }
+
+        (_, None) => "webview was not ready".into(),
+    }
+}
+
+async fn status(ctx: &Context) -> String {
+    let page = match ctx.screen_state.read().await.page() {
+        Page::LTScreen => "LT screen",
+        Page::WaitingScreen => "waiting screen",
+    };
+
+    let current = match ctx.current_presentation.read().await.as_ref() {
+        Some(x) => format!("{} by {}", x.title, x.presenter.name),
+        None => "none".into(),
+    };
+
+    let listening = match *ctx.listening_channel_id.read().await {
+        Some(id) => format!("<#{}>", id),
+        None => "none".into(),
+    };
+
+    #[cfg(feature = "obs")]
+    let obs = ctx.obs_status.read().await.to_string();
+
+    #[cfg(not(feature = "obs"))]
+    let obs = "obs feature is not enabled";
+
+    format!(
+        "page: {}\ncurrent: {}\nqueue: {} presentations\nlistening: {}\nobs: {}",
+        page,
+        current,
+        ctx.presentations.read().await.len(),
+        listening,
+        obs
+    )
+}
+
+/// posts `msg` to every enabled service and describes the results line by line.
+async fn post(ctx: &Context, msg: &str) -> String {
+    if ctx.social_posters.is_empty() {
+        tracing::warn!("Post simulation:\n{}", msg);
+        return "no service is enabled. nothing was posted.".into();
+    }
+
+    social::post_all(&ctx.social_posters, msg)
+        .await
+        .into_iter()
+        .map(|(name, result)| match result {
+            Ok(link) => format!("{}: {}", name, link),
+
+            Err(e) => {
+                tracing::error!("failed to post to {}: {:?}", name, e);
+                format!("{}: failed. read log for more details.", name)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn random_footer(ctx: &Context) -> String {
+    let index = rand::random::<u8>() % 3;
+
+    match index {
+        0 => {
+            format!(
+                include_str!("tweet_template/footer/youtube.fmt.txt"),
+                YOUTUBE_URL = ctx.sns_info.youtube_stream_url
+            )
+        }
+        1 => {
+            format!(
+                include_str!("tweet_template/footer/discord.fmt.txt"),
+                DISCORD_INVITATION_URL = ctx.sns_info.discord_invitation_url
+            )
+        }
+        2 => include_str!("tweet_template/footer/twitter.txt").to_string(),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_parse_control_command() {
+    let cmd = serde_json::from_str(r#"{"command": "presentation", "action": "pop"}"#).unwrap();
+    assert!(matches!(
+        cmd,
+        ControlCommand::Presentation(PresentationCommand::Pop)
+    ));
+
+    let cmd =
+        serde_json::from_str(r#"{"command": "timer", "action": "extend", "secs": 30}"#).unwrap();
+    assert!(matches!(
+        cmd,
+        ControlCommand::Timer(TimerCommand::Extend { secs: 30 })
+    ));
+
+    let cmd = serde_json::from_str(r#"{"command": "tweet", "msg": "hello"}"#).unwrap();
+    assert!(matches!(
+        cmd,
+        ControlCommand::Tweet {
+            simulation: false,
+            ..
+        }
+    ));
+
+    let cmd = serde_json::from_str(r#"{"command": "obs", "action": "stream_stop"}"#).unwrap();
+    assert!(matches!(
+        cmd,
+        ControlCommand::Obs(ObsCommand::StreamStop { confirmed: false })
+    ));
+
+    let cmd = serde_json::from_str(r#"{"command": "obs", "action": "screenshot", "scene": "LT"}"#)
+        .unwrap();
+    assert!(matches!(
+        cmd,
+        ControlCommand::Obs(ObsCommand::Screenshot { scene }) if scene == "LT"
+    ));
+
+    assert!(serde_json::from_str::<ControlCommand>(r#"{"command": "unknown"}"#).is_err());
+    assert!(serde_json::from_str::<ControlCommand>(
+        r#"{"command": "presentation", "action": "unpop"}"#
+    )
+    .is_err());
+}
+
+#[test]
+fn test_execute() {
+    let ctx = Context::for_test(None);
+
+    ctx.rt.block_on(async {
+        let reply = execute(&ctx, ControlCommand::Pause).await;
+        assert_eq!(reply, "webview was not ready");
+    });
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+    let ctx = Context::for_test(Some(tx));
+
+    ctx.rt.block_on(async {
+        let reply = execute(
+            &ctx,
+            ControlCommand::SetNotification {
+                text: "hello".into(),
+            },
+        )
+        .await;
+
+        assert_eq!(reply, "set");
+        assert!(matches!(
+            rx.recv().await,
+            Some(ScreenAction::NotificationUpdate { text }) if text == "hello"
+        ));
+
+        let reply = execute(&ctx, ControlCommand::Timer(TimerCommand::Pause)).await;
+        assert_eq!(reply, "timer is not running. pop a presentation first");
+
+        let reply = execute(&ctx, ControlCommand::Presentation(PresentationCommand::Pop)).await;
+        assert_eq!(reply, "no other entries in queue");
+
+        let reply = execute(
+            &ctx,
+            ControlCommand::MuteUser {
+                user: "youtube:troll".into(),
+                duration_secs: Some(600),
+            },
+        )
+        .await;
+
+        assert_eq!(reply, "muted youtube:troll for 600 secs");
+        assert!(matches!(
+            rx.recv().await,
+            Some(ScreenAction::TimelineRemoveUser { author }) if author == "youtube:troll"
+        ));
+
+        assert_eq!(undo(&ctx).await, "undone: unmuted youtube:troll");
+        assert_eq!(undo(&ctx).await, "nothing to undo");
+
+        execute(
+            &ctx,
+            ControlCommand::MuteUser {
+                user: "youtube:troll".into(),
+                duration_secs: None,
+            },
+        )
+        .await;
+        rx.recv().await;
+
+        let unmute = || ControlCommand::UnmuteUser {
+            user: "youtube:troll".into(),
+        };
+        assert_eq!(execute(&ctx, unmute()).await, "unmuted youtube:troll");
+        assert_eq!(execute(&ctx, unmute()).await, "youtube:troll is not muted");
+    });
+}
+
+#[test]
+fn test_undo_first_notification() {
+    let (tx, mut screen) = tokio::sync::mpsc::channel(100);
+    let ctx = Context::for_test(Some(tx));
+
+    ctx.rt.block_on(async {
+        // something undoable before it
+        remember(&ctx, ControlCommand::Resume).await;
+
+        let text = "始まります".to_string();
+        execute(&ctx, ControlCommand::SetNotification { text }).await;
+        undo(&ctx).await;
+    });
+
+    let mut texts = vec![];
+    while let Ok(action) = screen.try_recv() {
+        if let ScreenAction::NotificationUpdate { text } = action {
+            texts.push(text);
+        }
+    }
+
+    assert_eq!(texts, ["始まります", ""]);
+    assert!(matches!(
+        ctx.rt.block_on(ctx.undo_stack.read()).as_slice(),
+        [ControlCommand::Resume]
+    ));
+}
+
+#[test]
+fn test_undo_pop() {
+    use crate::{presentations::Presentations, test_util::presentation};
+
+    let (tx, mut screen) = tokio::sync::mpsc::channel(100);
+    let ctx = Context::for_test(Some(tx));
+
+    #[cfg(feature = "obs")]
+    let (obs_tx, mut obs_rx) = tokio::sync::mpsc::channel(10);
+
+    ctx.rt.block_on(async {
+        #[cfg(feature = "obs")]
+        {
+            *ctx.obs_chan.write().await = Some(obs_tx);
+        }
+
+        *ctx.presentations.write().await = Presentations::from_vec(vec![
+            presentation("alice", "aliceのLT", Some(300)),
+            presentation("bob", "bobのLT", Some(600)),
+        ]);
+
+        let pop = || ControlCommand::Presentation(PresentationCommand::Pop);
+        execute(&ctx, pop()).await;
+        execute(&ctx, pop()).await;
+    });
+
+    while screen.try_recv().is_ok() {}
+    #[cfg(feature = "obs")]
+    while obs_rx.try_recv().is_ok() {}
+
+    // bob goes back to the queue and alice is on again
+    ctx.rt.block_on(undo(&ctx));
+
+    let mut screen_actions = vec![];
+    while let Ok(action) = screen.try_recv() {
+        screen_actions.push(action);
+    }
+
+    assert!(screen_actions.iter().any(|x| matches!(
+        x,
+        ScreenAction::PresentationUpdate { presenter, .. } if presenter.name == "alice"
+    )));
+    assert!(screen_actions.iter().any(|x| matches!(
+        x,
+        ScreenAction::TimerUpdate {
+            remaining_secs: 300,
+            ..
+        }
+    )));
+    assert_eq!(ctx.rt.block_on(ctx.presentations.read()).len(), 1);
+
+    // OBS is told so, to cut the intro of bob short
+    #[cfg(feature = "obs")]
+    {
+        let mut obs_actions = vec![];
+        while let Ok(action) = obs_rx.try_recv() {
+            obs_actions.push(action);
+        }
+
+        assert!(matches!(
+            obs_actions.as_slice(),
+            [ObsAction::TalkCancelled { presenter, .. }] if presenter == "bob"
+        ));
+    }
+
+    // nothing was on before alice
+    ctx.rt.block_on(undo(&ctx));
+
+    let mut screen_actions = vec![];
+    while let Ok(action) = screen.try_recv() {
+        screen_actions.push(action);
+    }
+
+    assert!(screen_actions
+        .iter()
+        .any(|x| matches!(x, ScreenAction::PresentationClear)));
+    assert!(screen_actions
+        .iter()
+        .any(|x| matches!(x, ScreenAction::TimerClear)));
+    assert!(ctx.rt.block_on(ctx.timer.read()).is_none());
+    assert!(ctx.rt.block_on(ctx.current_presentation.read()).is_none());
+}
+
+#[test]
+fn test_undo_notification_set_back_to_back() {
+    let (tx, mut screen) = tokio::sync::mpsc::channel(100);
+    let ctx = Context::for_test(Some(tx));
+
+    // nothing consumes the actions in between, as when the webview is busy
+    ctx.rt.block_on(async {
+        let set = |text: &str| ControlCommand::SetNotification { text: text.into() };
+        execute(&ctx, set("始まります")).await;
+        execute(&ctx, set("休憩中")).await;
+        undo(&ctx).await;
+    });
+
+    let mut texts = vec![];
+    while let Ok(action) = screen.try_recv() {
+        if let ScreenAction::NotificationUpdate { text } = action {
+            texts.push(text);
+        }
+    }
+
+    assert_eq!(texts, ["始まります", "休憩中", "始まります"]);
+}
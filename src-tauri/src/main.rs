@@ -6,17 +6,42 @@
 
 // TODO: replace all pub -> pub(crate)
 
+mod alert;
+mod approval;
 mod client;
+mod control;
 mod model;
+mod moderation;
+mod outbox;
 mod presentations;
+mod social;
+mod state;
+mod state_file;
+mod timer;
 
 #[cfg(feature = "obs")]
 mod obs;
 
+#[cfg(feature = "browser_source")]
+mod browser_source;
+
+#[cfg(test)]
+mod test_util;
+
 use {
-    crate::{model::ScreenAction, presentations::Presentations},
+    crate::{
+        alert::Alerts,
+        approval::ApprovalQueue,
+        model::ScreenAction,
+        moderation::Moderator,
+        presentations::{Presentation, Presentations},
+        social::SocialPoster,
+        state::ScreenState,
+        state_file::StateFile,
+        timer::PresentationTimer,
+    },
     anyhow::{Context as _, Result},
-    std::{path::Path, sync::Arc},
+    std::{path::Path, sync::Arc, time::Duration},
     tokio::{
         runtime::{Builder as TokioRuntimeBuilder, Runtime as TokioRuntime},
         sync::{
@@ -31,6 +56,27 @@ use tauri::{App, Manager, Runtime};
 #[cfg(feature = "obs")]
 use crate::obs::ObsAction;
 
+const DEFAULT_PRESENTATION_DURATION: Duration = Duration::from_secs(5 * 60);
+
+const DEFAULT_STATE_FILE: &str = "./state.json";
+
+const DEFAULT_MODERATION_FILE: &str = "./moderation.yaml";
+
+#[cfg(feature = "twitter")]
+const DEFAULT_TWITTER_HASHTAG: &str = "#限界LT";
+
+#[cfg(feature = "twitter")]
+const DEFAULT_TWITTER_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[cfg(feature = "control_server")]
+const DEFAULT_CONTROL_SERVER_ADDRESS: &str = "127.0.0.1:7890";
+
+#[cfg(feature = "browser_source")]
+const DEFAULT_BROWSER_SOURCE_ADDRESS: &str = "127.0.0.1:7891";
+
+#[cfg(feature = "browser_source")]
+const SCREEN_EVENTS_CAPACITY: usize = 64;
+
 struct SnsInfo {
     youtube_stream_url: String,
     discord_invitation_url: String,
@@ -40,10 +86,79 @@ struct Context {
     rt: TokioRuntime,
     sns_info: SnsInfo,
     webview_chan: RwLock<Option<Sender<ScreenAction>>>,
+    screen_state: RwLock<ScreenState>,
+    // the notification sent to the webview last. screen_state catches up with it only
+    // after the webview consumes the action, too late to know what to undo to.
+    notification: RwLock<String>,
     presentations: RwLock<Presentations>,
+    current_presentation: RwLock<Option<Presentation>>,
+    finished_presentations: RwLock<Vec<Presentation>>,
+    listening_channel_id: RwLock<Option<u64>>,
+    timer: RwLock<Option<PresentationTimer>>,
+    default_presentation_duration: Duration,
+    state_file: StateFile,
+    social_posters: Vec<Box<dyn SocialPoster>>,
+    moderator: Moderator,
+    approval: ApprovalQueue,
+    alerts: Alerts,
+    // inverses of the commands executed recently, the latest last
+    undo_stack: RwLock<Vec<control::ControlCommand>>,
 
     #[cfg(feature = "obs")]
     obs_chan: RwLock<Option<Sender<ObsAction>>>,
+    #[cfg(feature = "obs")]
+    obs_status: RwLock<obs::ObsStatus>,
+
+    // what YoutubeListener listens. replaced by operators mid-event.
+    #[cfg(feature = "youtube")]
+    youtube_target: tokio::sync::watch::Sender<Option<client::youtube::YoutubeTarget>>,
+
+    // serialized screen events for views other than the tauri window
+    #[cfg(feature = "browser_source")]
+    screen_events: tokio::sync::broadcast::Sender<String>,
+}
+
+#[cfg(test)]
+impl Context {
+    fn for_test(webview_chan: Option<Sender<ScreenAction>>) -> Arc<Self> {
+        Arc::new(Context {
+            rt: TokioRuntimeBuilder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap(),
+            webview_chan: RwLock::new(webview_chan),
+            screen_state: RwLock::new(ScreenState::new()),
+            notification: RwLock::new(String::new()),
+            presentations: RwLock::new(Presentations::new()),
+            current_presentation: RwLock::new(None),
+            finished_presentations: RwLock::new(vec![]),
+            listening_channel_id: RwLock::new(None),
+            timer: RwLock::new(None),
+            default_presentation_duration: DEFAULT_PRESENTATION_DURATION,
+            state_file: StateFile::new(std::env::temp_dir().join("sugoi-test-state.json")),
+            social_posters: vec![],
+            moderator: Moderator::new(std::env::temp_dir().join("sugoi-test-moderation.yaml")),
+            approval: ApprovalQueue::new(false),
+            alerts: Alerts::new(false),
+            undo_stack: RwLock::new(vec![]),
+
+            sns_info: SnsInfo {
+                youtube_stream_url: String::new(),
+                discord_invitation_url: String::new(),
+            },
+
+            #[cfg(feature = "obs")]
+            obs_chan: RwLock::new(None),
+            #[cfg(feature = "obs")]
+            obs_status: RwLock::new(obs::ObsStatus::default()),
+
+            #[cfg(feature = "youtube")]
+            youtube_target: tokio::sync::watch::channel(None).0,
+
+            #[cfg(feature = "browser_source")]
+            screen_events: tokio::sync::broadcast::channel(SCREEN_EVENTS_CAPACITY).0,
+        })
+    }
 }
 
 fn env_var(name: &str) -> String {
@@ -53,6 +168,11 @@ fn env_var(name: &str) -> String {
     }
 }
 
+// empty values are treated as unset so that blank lines copied from .env.example disable features
+fn optional_env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|x| !x.is_empty())
+}
+
 fn main() -> Result<()> {
     dotenv::dotenv().ok();
 
@@ -68,19 +188,129 @@ fn main() -> Result<()> {
         .build()
         .context("Failed to create tokio runtime")?;
 
-    let presentations = rt
-        .block_on(Presentations::load_from_file(Path::new(
-            "./presentations.yaml",
-        )))
-        .context("failed to load presentations.yaml")?;
+    let resume = std::env::args().skip(1).any(|x| x == "--resume");
+
+    let state_file = StateFile::new(
+        optional_env_var("STATE_FILE")
+            .unwrap_or_else(|| DEFAULT_STATE_FILE.to_string())
+            .into(),
+    );
+
+    let mut screen_state = ScreenState::new();
+
+    let (presentations, current_presentation, finished_presentations, listening_channel_id) =
+        if resume {
+            let saved = rt.block_on(state_file.load()).with_context(|| {
+                format!("failed to resume from {}", state_file.path().display())
+            })?;
+
+            if let Some(text) = saved.notification {
+                screen_state.apply(&ScreenAction::NotificationUpdate { text });
+            }
+
+            if let Some(p) = &saved.current_presentation {
+                screen_state.apply(&ScreenAction::PresentationUpdate {
+                    presenter: p.presenter.clone(),
+                    title: p.title.clone(),
+                });
+            }
+
+            screen_state.apply(&ScreenAction::SwitchPage(saved.page));
+
+            tracing::info!("resumed from {}", state_file.path().display());
+
+            (
+                Presentations::from_vec(saved.presentations),
+                saved.current_presentation,
+                saved.finished_presentations,
+                saved.listening_channel_id,
+            )
+        } else {
+            let presentations = rt
+                .block_on(Presentations::load_from_file(Path::new(
+                    "./presentations.yaml",
+                )))
+                .context("failed to load presentations.yaml")?;
+
+            // the fresh state written below would otherwise replace the previous session
+            if let Some(backup) = rt.block_on(state_file.back_up())? {
+                tracing::info!(
+                    "moved the previous state to {}. rename it back and pass --resume to continue from it",
+                    backup.display()
+                );
+            }
+
+            (presentations, None, vec![], None)
+        };
 
     let youtube_stream_url = env_var("YOUTUBE_STREAM_URL");
     let discord_invitation_url = env_var("DISCORD_INVITATION_URL");
 
+    let default_presentation_duration = match optional_env_var("PRESENTATION_DURATION_SECS") {
+        Some(secs) => Duration::from_secs(
+            secs.parse()
+                .context("failed to decode PRESENTATION_DURATION_SECS")?,
+        ),
+        None => DEFAULT_PRESENTATION_DURATION,
+    };
+
+    let moderator = Moderator::new(
+        optional_env_var("MODERATION_FILE")
+            .unwrap_or_else(|| DEFAULT_MODERATION_FILE.to_string())
+            .into(),
+    );
+
+    rt.block_on(moderator.reload())
+        .with_context(|| format!("failed to load {}", moderator.path().display()))?;
+
+    // messages are held until approved in this channel if it's set
+    #[cfg(feature = "discord")]
+    let approval_channel_id = optional_env_var("APPROVAL_CHANNEL_ID")
+        .map(|x| x.parse::<u64>())
+        .transpose()
+        .context("failed to decode APPROVAL_CHANNEL_ID")?;
+
+    #[cfg(not(feature = "discord"))]
+    let approval_channel_id: Option<u64> = None;
+
+    // alerts are only logged if it's not set
+    #[cfg(feature = "discord")]
+    let alert_channel_id = optional_env_var("ALERT_CHANNEL_ID")
+        .map(|x| x.parse::<u64>())
+        .transpose()
+        .context("failed to decode ALERT_CHANNEL_ID")?;
+
+    #[cfg(not(feature = "discord"))]
+    let alert_channel_id: Option<u64> = None;
+
+    #[cfg(feature = "youtube")]
+    let (youtube_target, youtube_target_rx) = {
+        let target = optional_env_var("YOUTUBE_VIDEO_ID")
+            .or_else(|| optional_env_var("YOUTUBE_CHANNEL"))
+            .map(|x| client::youtube::YoutubeTarget::parse(&x))
+            .transpose()
+            .context("failed to decode YOUTUBE_VIDEO_ID or YOUTUBE_CHANNEL")?;
+
+        tokio::sync::watch::channel(target)
+    };
+
     let ctx = Arc::new(Context {
         rt,
         webview_chan: RwLock::new(None),
+        notification: RwLock::new(screen_state.notification().unwrap_or_default().to_owned()),
+        screen_state: RwLock::new(screen_state),
         presentations: RwLock::new(presentations),
+        current_presentation: RwLock::new(current_presentation),
+        finished_presentations: RwLock::new(finished_presentations),
+        listening_channel_id: RwLock::new(listening_channel_id),
+        timer: RwLock::new(None),
+        default_presentation_duration,
+        state_file,
+        social_posters: social::posters_from_env()?,
+        moderator,
+        approval: ApprovalQueue::new(approval_channel_id.is_some()),
+        alerts: Alerts::new(alert_channel_id.is_some()),
+        undo_stack: RwLock::new(vec![]),
 
         sns_info: SnsInfo {
             youtube_stream_url,
@@ -89,19 +319,35 @@ fn main() -> Result<()> {
 
         #[cfg(feature = "obs")]
         obs_chan: RwLock::new(None),
+        #[cfg(feature = "obs")]
+        obs_status: RwLock::new(obs::ObsStatus::default()),
+
+        #[cfg(feature = "youtube")]
+        youtube_target,
+
+        #[cfg(feature = "browser_source")]
+        screen_events: tokio::sync::broadcast::channel(SCREEN_EVENTS_CAPACITY).0,
     });
 
     std::mem::forget(Arc::clone(&ctx));
 
+    ctx.rt.spawn(timer::run_ticker(Arc::clone(&ctx)));
+    ctx.rt.spawn(state_file::run_saver(Arc::clone(&ctx)));
+    ctx.rt.spawn(moderation::run_reloader(Arc::clone(&ctx)));
+
+    // write the initial state right away so that we can resume even if nothing happens
+    ctx.state_file.request_save();
+
     #[cfg(feature = "discord")]
     {
-        use crate::client::discord::DiscordListener;
+        use crate::client::discord::{DiscordListener, Permissions};
         let discord_token = env_var("DISCORD_TOKEN");
+        let permissions = Permissions::from_env()?;
 
         let my_ctx = Arc::clone(&ctx);
 
         ctx.rt.spawn(async move {
-            DiscordListener::new(my_ctx)
+            DiscordListener::new(my_ctx, approval_channel_id, alert_channel_id, permissions)
                 .start(&discord_token)
                 .await
                 .context("failed to start discord listener")
@@ -111,44 +357,112 @@ fn main() -> Result<()> {
 
     #[cfg(feature = "youtube")]
     {
-        use crate::client::youtube::YoutubeListener;
+        use crate::client::youtube::{
+            DataApiChat, InnertubeChat, LiveChat, YoutubeBackend, YoutubeListener,
+        };
+
+        let backend = YoutubeBackend::parse(
+            &optional_env_var("YOUTUBE_CHAT_BACKEND").unwrap_or_else(|| "innertube".to_string()),
+        )?;
+
+        let chat: Box<dyn LiveChat> = match backend {
+            YoutubeBackend::Innertube => Box::new(InnertubeChat::new(
+                crate::client::youtube::INNERTUBE_BASE_URL.to_string(),
+            )),
+
+            YoutubeBackend::DataApi => Box::new(DataApiChat::new(
+                crate::client::youtube::DATA_API_BASE_URL.to_string(),
+                env_var("YOUTUBE_API_KEY"),
+            )),
+        };
+
+        let listener = YoutubeListener::new(Arc::clone(&ctx), chat, youtube_target_rx);
+
+        ctx.rt.spawn(listener.start());
+    }
+
+    #[cfg(feature = "twitter")]
+    if let Some(bearer_token) = optional_env_var("TWITTER_BEARER_TOKEN") {
+        use crate::{client::twitter::TwitterListener, social::twitter::DEFAULT_API_BASE};
+
+        let api_base =
+            optional_env_var("TWITTER_API_BASE").unwrap_or_else(|| DEFAULT_API_BASE.to_string());
+
+        let hashtag = optional_env_var("TWITTER_HASHTAG")
+            .unwrap_or_else(|| DEFAULT_TWITTER_HASHTAG.to_string());
+
+        let interval = match optional_env_var("TWITTER_POLL_INTERVAL_SECS") {
+            Some(secs) => Duration::from_secs(
+                secs.parse()
+                    .context("failed to decode TWITTER_POLL_INTERVAL_SECS")?,
+            ),
+            None => DEFAULT_TWITTER_POLL_INTERVAL,
+        };
+
+        let listener =
+            TwitterListener::new(Arc::clone(&ctx), api_base, bearer_token, &hashtag, interval);
+
+        ctx.rt.spawn(listener.start());
+    }
+
+    #[cfg(feature = "control_server")]
+    {
+        use crate::client::control_server::ControlServer;
+
+        let addr = optional_env_var("CONTROL_SERVER_ADDRESS")
+            .unwrap_or_else(|| DEFAULT_CONTROL_SERVER_ADDRESS.to_string())
+            .parse()
+            .context("failed to decode CONTROL_SERVER_ADDRESS")?;
+
+        let my_ctx = Arc::clone(&ctx);
 
-        let youtube_ctx = Arc::clone(&ctx);
         ctx.rt.spawn(async move {
-            // TODO: replace video id
-            YoutubeListener::new(youtube_ctx, "5VoIGGMYrDg".to_string())
-                .start()
-                .await;
+            ControlServer::new(my_ctx)
+                .start(addr)
+                .await
+                .context("failed to start control server")
+                .unwrap();
         });
     }
 
-    #[cfg(feature = "obs")]
+    #[cfg(feature = "browser_source")]
     {
-        use crate::obs::ObsClient;
+        use crate::browser_source::BrowserSource;
 
-        let addr = env_var("OBS_ADDRESS");
-        let pass = env_var("OBS_PASS");
-        let port = env_var("OBS_PORT")
+        let addr = optional_env_var("BROWSER_SOURCE_ADDRESS")
+            .unwrap_or_else(|| DEFAULT_BROWSER_SOURCE_ADDRESS.to_string())
             .parse()
-            .context("failed to decode OBS_PORT")?;
+            .context("failed to decode BROWSER_SOURCE_ADDRESS")?;
 
-        let (tx, rx) = channel(10);
+        let dist_dir = optional_env_var("BROWSER_SOURCE_DIST_DIR")
+            .unwrap_or_else(|| "../dist".to_string())
+            .into();
 
-        ctx.rt
-            .block_on(async { *ctx.obs_chan.write().await = Some(tx) });
+        let my_ctx = Arc::clone(&ctx);
 
         ctx.rt.spawn(async move {
-            ObsClient::connect(&addr, port, &pass)
+            BrowserSource::new(my_ctx, dist_dir)
+                .start(addr)
                 .await
-                .context("failed to initialize ObsClient")
-                .unwrap()
-                .start(rx)
-                .await
-                .context("error occur while running ObsClient")
-                .unwrap()
+                .context("failed to start browser source")
+                .unwrap();
         });
     }
 
+    #[cfg(feature = "obs")]
+    {
+        use crate::obs::ObsConfig;
+
+        let config = ObsConfig::from_env()?;
+        let (tx, rx) = channel(10);
+
+        ctx.rt
+            .block_on(async { *ctx.obs_chan.write().await = Some(tx) });
+
+        // keeps reconnecting while OBS is down
+        ctx.rt.spawn(obs::run(Arc::clone(&ctx), config, rx));
+    }
+
     tauri::Builder::default()
         .setup(move |x| {
             setup(Arc::clone(&ctx), x);
@@ -172,9 +486,45 @@ fn setup<R: Runtime>(ctx: Arc<Context>, app: &App<R>) {
 
     let win = app.get_window("main").unwrap();
 
+    // the window asks for a snapshot whenever it (re)loads
+    {
+        let ctx = Arc::clone(&ctx);
+        let win = win.clone();
+
+        app.listen_global("screen.ready", move |_| {
+            let my_ctx = Arc::clone(&ctx);
+            let win = win.clone();
+
+            ctx.rt.spawn(async move {
+                let state = my_ctx.screen_state.read().await;
+                win.emit("event", state.snapshot(&my_ctx).await).unwrap();
+            });
+        });
+    }
+
+    let my_ctx = Arc::clone(&ctx);
+
     ctx.rt.spawn(async move {
         while let Some(action) = rx.recv().await {
-            win.emit("event", action.serialize().await).unwrap();
+            // keep holding the lock until the event is emitted
+            // so that a snapshot never overtakes events it doesn't contain.
+            let mut state = my_ctx.screen_state.write().await;
+            state.apply(&action);
+
+            // timer is not persisted. no need to save every tick.
+            if !matches!(
+                action,
+                ScreenAction::TimerUpdate { .. } | ScreenAction::TimerOvertime { .. }
+            ) {
+                my_ctx.state_file.request_save();
+            }
+
+            let json = action.serialize().await;
+
+            #[cfg(feature = "browser_source")]
+            my_ctx.screen_events.send(json.clone()).ok();
+
+            win.emit("event", json).unwrap();
         }
     });
 }
@@ -1,7 +1,9 @@
 use {
     crate::Context,
+    once_cell::sync::Lazy,
+    regex::Regex,
     serde::{Deserialize, Serialize},
-    serde_json::json,
+    serde_json::{json, Value},
     std::sync::Arc,
 };
 
@@ -12,7 +14,7 @@ pub struct User {
     pub name: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum Service {
     Discord,
@@ -20,7 +22,209 @@ pub enum Service {
     Youtube,
 }
 
-#[derive(Serialize)]
+impl Service {
+    /// same as the serialized name
+    pub fn name(self) -> &'static str {
+        match self {
+            Service::Discord => "discord",
+            Service::Twitter => "twitter",
+            Service::Youtube => "youtube",
+        }
+    }
+}
+
+/// a piece of the content of a timeline item.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Segment {
+    Text {
+        text: String,
+    },
+    Emoji {
+        url: String,
+        alt: String,
+    },
+    /// already resolved to a display name like "@name" or "#channel"
+    Mention {
+        name: String,
+    },
+    Link {
+        url: String,
+        text: String,
+    },
+    Code {
+        text: String,
+    },
+}
+
+impl Segment {
+    fn len(&self) -> usize {
+        match self {
+            Segment::Text { text } | Segment::Link { text, .. } | Segment::Code { text } => {
+                text.chars().count()
+            }
+            Segment::Emoji { .. } => 1,
+            Segment::Mention { name } => name.chars().count(),
+        }
+    }
+}
+
+/// content of a timeline item. serialized as an array of segments.
+#[derive(Serialize, Clone, Debug, PartialEq, Default)]
+#[serde(transparent)]
+pub struct Content(Vec<Segment>);
+
+impl Content {
+    pub fn new() -> Self {
+        Self(vec![])
+    }
+
+    /// splits URLs in `text` into links.
+    pub fn from_text(text: &str) -> Self {
+        let mut content = Self::new();
+        content.push_text_with_links(text);
+        content
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.0
+    }
+
+    /// joins adjacent texts so that the frontend doesn't have to.
+    pub fn push(&mut self, segment: Segment) {
+        match (self.0.last_mut(), segment) {
+            (_, Segment::Text { text }) if text.is_empty() => {}
+
+            (Some(Segment::Text { text: last }), Segment::Text { text }) => last.push_str(&text),
+
+            (_, segment) => self.0.push(segment),
+        }
+    }
+
+    pub fn push_text(&mut self, text: &str) {
+        self.push(Segment::Text { text: text.into() });
+    }
+
+    pub fn push_text_with_links(&mut self, text: &str) {
+        static URL: Lazy<Regex> = Lazy::new(|| Regex::new(r"https?://[^\s<>]+").unwrap());
+
+        let mut last = 0;
+
+        for m in URL.find_iter(text) {
+            self.push_text(&text[last..m.start()]);
+
+            self.push(Segment::Link {
+                url: m.as_str().into(),
+                text: m.as_str().into(),
+            });
+
+            last = m.end();
+        }
+
+        self.push_text(&text[last..]);
+    }
+
+    /// length in characters as shown on the screen. an emoji counts as one.
+    pub fn len(&self) -> usize {
+        self.0.iter().map(Segment::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn has_links(&self) -> bool {
+        self.0.iter().any(|x| matches!(x, Segment::Link { .. }))
+    }
+
+    /// removes links entirely. returns false if there was none.
+    pub fn strip_links(&mut self) -> bool {
+        if !self.has_links() {
+            return false;
+        }
+
+        let segments = std::mem::take(&mut self.0);
+
+        for segment in segments {
+            if !matches!(segment, Segment::Link { .. }) {
+                self.push(segment);
+            }
+        }
+
+        true
+    }
+
+    /// cuts the content to `max` characters followed by "…". returns false if it was short enough.
+    /// a segment other than text on the boundary is dropped as a whole.
+    pub fn truncate(&mut self, max: usize) -> bool {
+        if self.len() <= max {
+            return false;
+        }
+
+        let segments = std::mem::take(&mut self.0);
+        let mut remaining = max;
+
+        for segment in segments {
+            let len = segment.len();
+
+            if len <= remaining {
+                remaining -= len;
+                self.push(segment);
+                continue;
+            }
+
+            if let Segment::Text { text } = segment {
+                self.push_text(&text.chars().take(remaining).collect::<String>());
+            }
+
+            break;
+        }
+
+        self.push_text("…");
+
+        true
+    }
+
+    /// how the content reads as plain text. emojis become their alt text.
+    pub fn plain_text(&self) -> String {
+        self.0
+            .iter()
+            .map(|x| match x {
+                Segment::Text { text } | Segment::Link { text, .. } | Segment::Code { text } => {
+                    text.as_str()
+                }
+                Segment::Emoji { alt, .. } => alt.as_str(),
+                Segment::Mention { name } => name.as_str(),
+            })
+            .collect()
+    }
+}
+
+/// paid or membership messages, which are emphasized on the screen.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Highlight {
+    SuperChat {
+        amount: String,
+        /// CSS color of the tier
+        color: String,
+        /// 1 (lowest) to 7 (highest) if known
+        tier: Option<u8>,
+    },
+    SuperSticker {
+        amount: String,
+        color: String,
+        tier: Option<u8>,
+        /// image URL of the sticker
+        sticker: Option<String>,
+    },
+    Membership {
+        /// e.g. "新規メンバー" or "メンバー歴 3 か月"
+        header: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
 pub enum Page {
     LTScreen,
     WaitingScreen,
@@ -29,9 +233,26 @@ pub enum Page {
 pub(crate) enum ScreenAction {
     TimelineClear,
     TimelinePush {
+        id: u64,
+        /// "<service>:<author>", which identifies the author across items
+        author: String,
+        /// ID of the message in the service, e.g. discord message ID
+        source_id: Option<String>,
         user: User,
         service: Service,
-        content: String,
+        content: Content,
+        highlight: Option<Highlight>,
+    },
+    TimelineUpdate {
+        id: u64,
+        content: Content,
+    },
+    TimelineRemove {
+        id: u64,
+    },
+    /// removes every item by the author, which is in the same form as `TimelinePush::author`
+    TimelineRemoveUser {
+        author: String,
     },
     NotificationUpdate {
         text: String,
@@ -40,8 +261,60 @@ pub(crate) enum ScreenAction {
         presenter: User,
         title: String,
     },
+    /// no presentation is ongoing, e.g. after undoing the first pop
+    PresentationClear,
     SwitchPage(Page),
     UpcomingPresentationsUpdate(Arc<Context>),
+    TimerUpdate {
+        remaining_secs: u64,
+        paused: bool,
+    },
+    TimerOvertime {
+        overtime_secs: u64,
+        paused: bool,
+    },
+    TimerClear,
+}
+
+pub(crate) fn user_json(user: &User) -> Value {
+    json!({
+        "userIcon": user.icon,
+        "identifier": user.ident,
+        "name": user.name
+    })
+}
+
+pub(crate) fn timeline_card_json(
+    id: u64,
+    author: &str,
+    user: &User,
+    service: Service,
+    content: &Content,
+    highlight: Option<&Highlight>,
+) -> Value {
+    json!({
+        "id": id,
+        "author": author,
+        "user": user_json(user),
+        "service": service,
+        "content": content,
+        "highlight": highlight,
+    })
+}
+
+pub(crate) fn presentation_json(presenter: &User, title: &str) -> Value {
+    json!({
+        "presenter": user_json(presenter),
+        "title": title
+    })
+}
+
+pub(crate) fn timer_json(seconds: u64, paused: bool, overtime: bool) -> Value {
+    json!({
+        "seconds": seconds,
+        "paused": paused,
+        "overtime": overtime
+    })
 }
 
 impl ScreenAction {
@@ -51,24 +324,35 @@ impl ScreenAction {
             TimelineClear => json!({ "type": "timeline.flush" }),
 
             TimelinePush {
+                id,
+                author,
+                source_id: _,
                 user,
                 service,
                 content,
+                highlight,
             } => json!({
                 "type": "timeline.add",
                 "args": {
-                    "new": {
-                        "user": {
-                            "userIcon": user.icon,
-                            "identifier": user.ident,
-                            "name": user.name
-                        },
-                        "service": service,
-                        "content": content,
-                    }
+                    "new": timeline_card_json(id, &author, &user, service, &content, highlight.as_ref())
                 }
             }),
 
+            TimelineUpdate { id, content } => json!({
+                "type": "timeline.update",
+                "args": { "id": id, "content": content }
+            }),
+
+            TimelineRemove { id } => json!({
+                "type": "timeline.remove",
+                "args": { "id": id }
+            }),
+
+            TimelineRemoveUser { author } => json!({
+                "type": "timeline.remove_user",
+                "args": { "author": author }
+            }),
+
             NotificationUpdate { text } => json!({
                 "type": "notification.update",
                 "args": {
@@ -79,17 +363,12 @@ impl ScreenAction {
             PresentationUpdate { presenter, title } => json!({
                 "type": "presentation.update",
                 "args": {
-                    "new": {
-                        "presenter": {
-                            "userIcon": presenter.icon,
-                            "identifier": presenter.ident,
-                            "name": presenter.name
-                        },
-                        "title": title
-                    }
+                    "new": presentation_json(&presenter, &title)
                 }
             }),
 
+            PresentationClear => json!({ "type": "presentation.clear" }),
+
             SwitchPage(page) => json!({
                 "type": "screen.update",
                 "args": {
@@ -103,8 +382,85 @@ impl ScreenAction {
                     "new": ctx.presentations.read().await.to_json_value()
                 }
             }),
+
+            TimerUpdate {
+                remaining_secs,
+                paused,
+            } => json!({
+                "type": "timer.update",
+                "args": {
+                    "new": timer_json(remaining_secs, paused, false)
+                }
+            }),
+
+            TimerOvertime {
+                overtime_secs,
+                paused,
+            } => json!({
+                "type": "timer.update",
+                "args": {
+                    "new": timer_json(overtime_secs, paused, true)
+                }
+            }),
+
+            TimerClear => json!({ "type": "timer.clear" }),
         };
 
         serde_json::to_string(&json).unwrap()
     }
 }
+
+#[test]
+fn test_content() {
+    let content = Content::from_text("see https://example.com/a?b=c and http://x.y");
+
+    assert_eq!(
+        content.segments(),
+        [
+            Segment::Text {
+                text: "see ".into()
+            },
+            Segment::Link {
+                url: "https://example.com/a?b=c".into(),
+                text: "https://example.com/a?b=c".into(),
+            },
+            Segment::Text {
+                text: " and ".into()
+            },
+            Segment::Link {
+                url: "http://x.y".into(),
+                text: "http://x.y".into(),
+            },
+        ]
+    );
+
+    let mut content = Content::new();
+    content.push_text("a");
+    content.push_text("");
+    content.push_text("b");
+    content.push(Segment::Emoji {
+        url: "https://example.com/e.png".into(),
+        alt: ":e:".into(),
+    });
+
+    assert_eq!(content.segments().len(), 2);
+    assert_eq!(content.plain_text(), "ab:e:");
+    assert_eq!(content.len(), 3);
+
+    assert_eq!(
+        serde_json::to_value(&content).unwrap(),
+        json!([
+            { "type": "text", "text": "ab" },
+            { "type": "emoji", "url": "https://example.com/e.png", "alt": ":e:" },
+        ])
+    );
+
+    let mut content = Content::from_text("see https://example.com and more");
+    assert!(content.strip_links());
+    assert!(!content.strip_links());
+    assert_eq!(content.plain_text(), "see  and more");
+
+    assert!(content.truncate(6));
+    assert!(!content.truncate(7));
+    assert_eq!(content.plain_text(), "see  a…");
+}
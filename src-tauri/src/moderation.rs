@@ -0,0 +1,602 @@
+use {
+    crate::{
+        model::{Content, Highlight, ScreenAction, Service, User},
+        Context,
+    },
+    anyhow::{Context as _, Result},
+    parking_lot::{Mutex, RwLock},
+    regex::{Regex, RegexBuilder},
+    serde::Deserialize,
+    std::{
+        collections::{HashMap, HashSet, VecDeque},
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        time::{Duration, Instant, SystemTime},
+    },
+    tokio::{fs, time::interval},
+};
+
+const RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// a message from a source before it's shown on the timeline.
+pub(crate) struct Incoming {
+    pub(crate) service: Service,
+    /// stable identifier of the author within the service, e.g. discord user ID or youtube name.
+    pub(crate) author: String,
+    /// ID of the message in the service if it can be referred later, e.g. discord message ID
+    pub(crate) source_id: Option<String>,
+    pub(crate) user: User,
+    pub(crate) content: Content,
+    pub(crate) highlight: Option<Highlight>,
+}
+
+impl Incoming {
+    /// the form used in `blocked_users` and `TimelinePush::author`, e.g. "discord:1234"
+    pub(crate) fn key(&self) -> String {
+        author_key(self.service, &self.author)
+    }
+
+    /// one line to show to operators, e.g. "[youtube] name: text"
+    pub(crate) fn describe(&self) -> String {
+        format!(
+            "[{}] {}: {}",
+            self.service.name(),
+            self.user.name,
+            self.content.plain_text()
+        )
+    }
+}
+
+pub(crate) fn author_key(service: Service, author: &str) -> String {
+    format!("{}:{}", service.name(), author)
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+enum UrlPolicy {
+    #[default]
+    Keep,
+    Strip,
+    Drop,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+struct RateLimit {
+    messages: usize,
+    per_secs: u64,
+}
+
+/// contents of the moderation file.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RulesFile {
+    /// matched case-insensitively
+    ng_words: Vec<String>,
+    ng_patterns: Vec<String>,
+    /// "<service>:<author>", e.g. "discord:1234" or "youtube:name"
+    blocked_users: Vec<String>,
+    urls: UrlPolicy,
+    max_length: Option<usize>,
+    rate_limit: Option<RateLimit>,
+}
+
+#[derive(Default)]
+struct Rules {
+    ng: Vec<Regex>,
+    blocked_users: HashSet<String>,
+    urls: UrlPolicy,
+    max_length: Option<usize>,
+    rate_limit: Option<RateLimit>,
+}
+
+impl Rules {
+    fn parse(yaml: &str) -> Result<Self> {
+        let file = serde_yaml::from_str::<Option<RulesFile>>(yaml)
+            .context("failed to deserialize")?
+            .unwrap_or_default();
+
+        let words = file.ng_words.iter().map(|x| regex::escape(x));
+
+        let ng = words
+            .chain(file.ng_patterns)
+            .map(|x| {
+                RegexBuilder::new(&x)
+                    .case_insensitive(true)
+                    .build()
+                    .with_context(|| format!("invalid pattern {:?}", x))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            ng,
+            blocked_users: file.blocked_users.into_iter().collect(),
+            urls: file.urls,
+            max_length: file.max_length,
+            rate_limit: file.rate_limit,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Verdict {
+    /// with the reasons of the edits, if any
+    Pass(Vec<String>),
+    Drop(String),
+}
+
+/// checks messages against the rules in a YAML file, which is reloaded when it's modified.
+pub(crate) struct Moderator {
+    path: PathBuf,
+    rules: RwLock<Rules>,
+    modified: Mutex<Option<SystemTime>>,
+
+    // when recent messages of each author were accepted
+    history: Mutex<HashMap<String, VecDeque<Instant>>>,
+
+    // muted by operators until the time, or forever if None
+    muted: Mutex<HashMap<String, Option<Instant>>>,
+}
+
+impl Moderator {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            rules: RwLock::new(Rules::default()),
+            modified: Mutex::new(None),
+            history: Mutex::new(HashMap::new()),
+            muted: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// drops messages from `author` (in the form of `author_key`) for a while, or until unmuted.
+    pub(crate) fn mute(&self, author: String, duration: Option<Duration>) {
+        let until = duration.map(|x| Instant::now() + x);
+        self.muted.lock().insert(author, until);
+    }
+
+    /// returns false if the author was not muted
+    pub(crate) fn unmute(&self, author: &str) -> bool {
+        self.muted.lock().remove(author).is_some()
+    }
+
+    fn is_muted(&self, author: &str, now: Instant) -> bool {
+        let mut muted = self.muted.lock();
+
+        match muted.get(author) {
+            Some(Some(until)) if *until <= now => {
+                muted.remove(author);
+                false
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// loads the file if it has changed since the last load. a missing file means no rules.
+    /// the current rules are kept on errors.
+    pub(crate) async fn reload(&self) -> Result<()> {
+        let modified = match fs::metadata(&self.path).await {
+            Ok(m) => Some(m.modified().context("failed to get modified time")?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e).context("failed to stat file"),
+        };
+
+        if *self.modified.lock() == modified {
+            return Ok(());
+        }
+
+        // failing to read may be transient. it's tried again next time.
+        let yaml = match modified {
+            Some(_) => Some(
+                fs::read_to_string(&self.path)
+                    .await
+                    .context("failed to read file")?,
+            ),
+            None => None,
+        };
+
+        // remember it before parsing so that a broken file is reported only once
+        *self.modified.lock() = modified;
+
+        let rules = match yaml {
+            Some(yaml) => Rules::parse(&yaml)?,
+            None => Rules::default(),
+        };
+
+        *self.rules.write() = rules;
+
+        tracing::info!("loaded moderation rules from {}", self.path.display());
+
+        Ok(())
+    }
+
+    /// applies the rules to `incoming`, editing its content if needed.
+    /// edits of messages already shown are not counted for the rate limit.
+    fn moderate(&self, incoming: &mut Incoming, now: Instant, is_edit: bool) -> Verdict {
+        let rules = self.rules.read();
+        let key = incoming.key();
+
+        if rules.blocked_users.contains(&key) {
+            return Verdict::Drop("blocked user".into());
+        }
+
+        if self.is_muted(&key, now) {
+            return Verdict::Drop("muted user".into());
+        }
+
+        let rate_limit = rules.rate_limit.filter(|_| !is_edit);
+
+        if let Some(limit) = rate_limit {
+            let mut history = self.history.lock();
+            let window = Duration::from_secs(limit.per_secs);
+
+            // forgets authors who have stopped posting as well
+            history.retain(|_, sent| {
+                while sent
+                    .front()
+                    .map_or(false, |x| now.saturating_duration_since(*x) >= window)
+                {
+                    sent.pop_front();
+                }
+
+                !sent.is_empty()
+            });
+
+            if history.get(&key).map_or(0, VecDeque::len) >= limit.messages {
+                return Verdict::Drop(format!(
+                    "rate limit ({} messages per {}s)",
+                    limit.messages, limit.per_secs
+                ));
+            }
+        }
+
+        let text = incoming.content.plain_text();
+
+        if let Some(ng) = rules.ng.iter().find(|x| x.is_match(&text)) {
+            return Verdict::Drop(format!("NG pattern {:?}", ng.as_str()));
+        }
+
+        let mut edits = vec![];
+
+        match rules.urls {
+            UrlPolicy::Keep => {}
+
+            UrlPolicy::Strip => {
+                if incoming.content.strip_links() {
+                    edits.push("stripped URLs".to_string());
+                }
+            }
+
+            UrlPolicy::Drop => {
+                if incoming.content.has_links() {
+                    return Verdict::Drop("contains URL".into());
+                }
+            }
+        }
+
+        if let Some(max) = rules.max_length {
+            if incoming.content.truncate(max) {
+                edits.push(format!("truncated to {} characters", max));
+            }
+        }
+
+        // paid messages are shown even without text
+        if incoming.content.is_empty() && incoming.highlight.is_none() {
+            return Verdict::Drop("empty content".into());
+        }
+
+        // only messages actually shown count
+        if rate_limit.is_some() {
+            self.history.lock().entry(key).or_default().push_back(now);
+        }
+
+        Verdict::Pass(edits)
+    }
+}
+
+/// reloads the moderation file whenever it's modified.
+pub(crate) async fn run_reloader(ctx: Arc<Context>) {
+    let mut interval = interval(RELOAD_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = ctx.moderator.reload().await {
+            tracing::error!(
+                "failed to reload moderation rules from {}: {:?}",
+                ctx.moderator.path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// moderates a message and pushes it to the timeline if it passes.
+/// it's held in the approval queue instead if pre-moderation is enabled.
+pub(crate) async fn submit(ctx: &Context, mut incoming: Incoming) {
+    match ctx.moderator.moderate(&mut incoming, Instant::now(), false) {
+        Verdict::Pass(edits) => log_edits(&incoming, &edits),
+
+        Verdict::Drop(reason) => {
+            tracing::info!(
+                "dropped message from {} ({}) for {}: {}",
+                incoming.user.name,
+                incoming.key(),
+                reason,
+                incoming.content.plain_text()
+            );
+
+            return;
+        }
+    }
+
+    if ctx.approval.is_enabled() {
+        ctx.approval.enqueue(incoming);
+        return;
+    }
+
+    push(ctx, incoming).await;
+}
+
+fn log_edits(incoming: &Incoming, edits: &[String]) {
+    if !edits.is_empty() {
+        tracing::info!(
+            "edited message from {} ({}): {}",
+            incoming.user.name,
+            incoming.key(),
+            edits.join(", ")
+        );
+    }
+}
+
+pub(crate) async fn push(ctx: &Context, incoming: Incoming) {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+    send(
+        ctx,
+        ScreenAction::TimelinePush {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            author: incoming.key(),
+            source_id: incoming.source_id,
+            user: incoming.user,
+            service: incoming.service,
+            content: incoming.content,
+            highlight: incoming.highlight,
+        },
+    )
+    .await;
+}
+
+/// replaces the content of the item shown or pending approval for the same message as `incoming`.
+/// the item is removed if the new content doesn't pass, or held for approval again if pre-moderated.
+pub(crate) async fn submit_edit(ctx: &Context, mut incoming: Incoming) {
+    let Some(source_id) = incoming.source_id.clone() else {
+        return;
+    };
+
+    let shown = find_by_source(ctx, &incoming).await;
+    let pending = ctx.approval.contains_source(incoming.service, &source_id);
+
+    if shown.is_none() && !pending {
+        return;
+    }
+
+    match ctx.moderator.moderate(&mut incoming, Instant::now(), true) {
+        Verdict::Pass(edits) => {
+            log_edits(&incoming, &edits);
+
+            if pending {
+                tracing::info!(
+                    "pending message from {} ({}) is replaced with the edited one",
+                    incoming.user.name,
+                    incoming.key()
+                );
+
+                ctx.approval.replace_by_source(incoming);
+            } else if let Some(id) = shown {
+                if ctx.approval.is_enabled() {
+                    tracing::info!(
+                        "edited message from {} ({}) is held for approval again",
+                        incoming.user.name,
+                        incoming.key()
+                    );
+
+                    send(ctx, ScreenAction::TimelineRemove { id }).await;
+                    ctx.approval.enqueue(incoming);
+                } else {
+                    send(
+                        ctx,
+                        ScreenAction::TimelineUpdate {
+                            id,
+                            content: incoming.content,
+                        },
+                    )
+                    .await;
+                }
+            }
+        }
+
+        Verdict::Drop(reason) => {
+            tracing::info!(
+                "removed edited message from {} ({}) for {}: {}",
+                incoming.user.name,
+                incoming.key(),
+                reason,
+                incoming.content.plain_text()
+            );
+
+            if let Some(id) = shown {
+                send(ctx, ScreenAction::TimelineRemove { id }).await;
+            }
+
+            ctx.approval.remove_by_source(incoming.service, &source_id);
+        }
+    }
+}
+
+/// removes the item shown or pending approval for the message `source_id` of `service` if any.
+pub(crate) async fn remove_by_source(ctx: &Context, service: Service, source_id: &str) {
+    if ctx.approval.remove_by_source(service, source_id) {
+        tracing::info!(
+            "removed pending message {} of {}",
+            source_id,
+            service.name()
+        );
+    }
+
+    let id = ctx
+        .screen_state
+        .read()
+        .await
+        .find_by_source(service, source_id);
+
+    if let Some(id) = id {
+        send(ctx, ScreenAction::TimelineRemove { id }).await;
+    }
+}
+
+async fn find_by_source(ctx: &Context, incoming: &Incoming) -> Option<u64> {
+    let source_id = incoming.source_id.as_deref()?;
+
+    ctx.screen_state
+        .read()
+        .await
+        .find_by_source(incoming.service, source_id)
+}
+
+async fn send(ctx: &Context, action: ScreenAction) {
+    match ctx.webview_chan.read().await.as_ref() {
+        Some(chan) => {
+            chan.send(action).await.ok();
+        }
+
+        None => tracing::warn!("couldn't update timeline because WebView was not initialized"),
+    }
+}
+
+#[test]
+fn test_moderate() {
+    let moderator = Moderator::new(PathBuf::new());
+
+    *moderator.rules.write() = Rules::parse(
+        r#"
+ng_words: ["Spam"]
+ng_patterns: ['(?:buy|sell) now']
+blocked_users: ["youtube:troll"]
+urls: strip
+max_length: 10
+rate_limit:
+  messages: 2
+  per_secs: 10
+"#,
+    )
+    .unwrap();
+
+    let incoming =
+        |author: &str, text: &str| crate::test_util::incoming(Service::Youtube, author, text);
+
+    let t0 = Instant::now();
+    let secs = Duration::from_secs;
+
+    let check = |author: &str, text: &str, now: Instant| {
+        let mut x = incoming(author, text);
+        let verdict = moderator.moderate(&mut x, now, false);
+        (verdict, x.content.plain_text())
+    };
+
+    assert_eq!(
+        check("troll", "hi", t0).0,
+        Verdict::Drop("blocked user".into())
+    );
+
+    assert_eq!(
+        check("a", "hello", t0),
+        (Verdict::Pass(vec![]), "hello".into())
+    );
+
+    assert_eq!(
+        check("b", "SPAM!", t0).0,
+        Verdict::Drop("NG pattern \"Spam\"".into())
+    );
+
+    assert_eq!(
+        check("c", "BUY NOW", t0).0,
+        Verdict::Drop("NG pattern \"(?:buy|sell) now\"".into())
+    );
+
+    assert_eq!(
+        check("a", "see https://example.com and more", t0),
+        (
+            Verdict::Pass(vec![
+                "stripped URLs".into(),
+                "truncated to 10 characters".into()
+            ]),
+            "see  and m…".into()
+        )
+    );
+
+    assert_eq!(
+        check("a", "third", t0 + secs(5)).0,
+        Verdict::Drop("rate limit (2 messages per 10s)".into())
+    );
+
+    assert_eq!(check("a", "fourth", t0 + secs(10)).0, Verdict::Pass(vec![]));
+
+    // the dropped spam doesn't count
+    assert_eq!(check("b", "one", t0).0, Verdict::Pass(vec![]));
+    assert_eq!(check("b", "two", t0).0, Verdict::Pass(vec![]));
+
+    moderator.mute("youtube:e".into(), Some(secs(60)));
+    assert_eq!(check("e", "hi", t0).0, Verdict::Drop("muted user".into()));
+    assert_eq!(check("e", "hi", t0 + secs(61)).0, Verdict::Pass(vec![]));
+
+    assert_eq!(
+        check("d", "https://example.com", t0).0,
+        Verdict::Drop("empty content".into())
+    );
+
+    assert_eq!(check("f", "late", t0 + secs(100)).0, Verdict::Pass(vec![]));
+    assert_eq!(
+        moderator.history.lock().keys().collect::<Vec<_>>(),
+        ["youtube:f"]
+    );
+
+    assert!(Rules::parse("ng_patterns: ['(']").is_err());
+    assert!(Rules::parse("").unwrap().ng.is_empty());
+}
+
+#[tokio::test]
+async fn test_reload_after_read_error() {
+    let path = std::env::temp_dir().join("sugoi-test-moderation-reload.yaml");
+    fs::remove_file(&path).await.ok();
+    fs::remove_dir(&path).await.ok();
+
+    // can be stat but not read
+    fs::create_dir(&path).await.unwrap();
+
+    let moderator = Moderator::new(path.clone());
+    assert!(moderator.reload().await.is_err());
+    assert!(moderator.reload().await.is_err());
+
+    fs::remove_dir(&path).await.unwrap();
+    fs::write(&path, "ng_words: [spam]").await.unwrap();
+    moderator.reload().await.unwrap();
+    assert!(!moderator.rules.read().ng.is_empty());
+
+    // broken yaml is reported only once
+    fs::write(&path, "ng_words: [").await.unwrap();
+    let modified = fs::metadata(&path).await.unwrap().modified().unwrap();
+    *moderator.modified.lock() = None;
+    assert!(moderator.reload().await.is_err());
+    assert_eq!(*moderator.modified.lock(), Some(modified));
+    assert!(moderator.reload().await.is_ok());
+
+    fs::remove_file(&path).await.ok();
+}
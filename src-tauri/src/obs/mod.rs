@@ -0,0 +1,1306 @@
+mod recording;
+
+use recording::RecordingConfig;
+
+use {
+    self::recording::{sanitize_file_name, talk_file_path, RecordingMode, TalkLog},
+    crate::{control::ObsCommand, env_var, model::Page, optional_env_var, Context},
+    anyhow::{Context as _, Result},
+    obws::{
+        requests::{inputs::Volume, sources::SaveScreenshot},
+        responses::streaming::StreamStatus,
+        Client,
+    },
+    std::{
+        collections::VecDeque,
+        convert::TryInto,
+        fmt,
+        path::PathBuf,
+        sync::Arc,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+    tokio::sync::{mpsc::Receiver, oneshot},
+    tokio::time::Instant,
+};
+
+#[derive(Debug)]
+pub(crate) enum ObsAction {
+    Mute,
+    UnMute,
+    /// switches to the scene for the page
+    ShowPage(Page),
+    /// shows the intro scene for a while, then goes back to the scene of the current page.
+    /// only switching pages cuts it short.
+    PresenterIntro,
+    /// starts recording the talk. it ends when the waiting screen is shown.
+    TalkStarted {
+        presenter: String,
+        title: String,
+    },
+    /// the talk was popped by mistake. it's forgotten and its recording is stopped, and the
+    /// intro for it is cut short.
+    TalkCancelled {
+        presenter: String,
+        title: String,
+    },
+    /// carried out by request of operators. the result is replied with the stream health.
+    Command {
+        command: ObsCommand,
+        reply: oneshot::Sender<String>,
+    },
+}
+
+impl ObsAction {
+    /// a copy to apply again after reconnecting. commands are answered rather than retried,
+    /// and the intro is stale by then.
+    fn retry(&self) -> Option<Self> {
+        Some(match self {
+            Self::Mute => Self::Mute,
+            Self::UnMute => Self::UnMute,
+            Self::ShowPage(page) => Self::ShowPage(*page),
+            Self::PresenterIntro | Self::Command { .. } => return None,
+            Self::TalkStarted { presenter, title } => Self::TalkStarted {
+                presenter: presenter.clone(),
+                title: title.clone(),
+            },
+            Self::TalkCancelled { presenter, title } => Self::TalkCancelled {
+                presenter: presenter.clone(),
+                title: title.clone(),
+            },
+        })
+    }
+}
+
+const DEFAULT_DUCK_DB: f32 = -20.0;
+const DEFAULT_FADE: Duration = Duration::from_millis(1000);
+const FADE_INTERVAL: Duration = Duration::from_millis(50);
+
+// OBS doesn't go below this. -inf of silent inputs is also treated as this.
+const MIN_DB: f32 = -100.0;
+
+enum MuteTargets {
+    All,
+    Only(Vec<String>),
+}
+
+/// which inputs are muted or ducked on the waiting screen
+pub(crate) struct AudioConfig {
+    mute: MuteTargets,
+    duck: Vec<String>,
+    duck_db: f32,
+    fade: Duration,
+}
+
+fn names_from_env(name: &str) -> Option<Vec<String>> {
+    let names = optional_env_var(name)?
+        .split(',')
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+        .collect();
+
+    Some(names)
+}
+
+impl AudioConfig {
+    pub(crate) fn from_env() -> Result<Self> {
+        let duck = names_from_env("OBS_DUCK_INPUTS").unwrap_or_default();
+
+        // every input is muted as before unless anything is configured
+        let mute = match names_from_env("OBS_MUTE_INPUTS") {
+            Some(names) => MuteTargets::Only(names),
+            None if duck.is_empty() => MuteTargets::All,
+            None => MuteTargets::Only(vec![]),
+        };
+
+        let duck_db = match optional_env_var("OBS_DUCK_DB") {
+            Some(db) => db.parse().context("failed to decode OBS_DUCK_DB")?,
+            None => DEFAULT_DUCK_DB,
+        };
+
+        let fade = match optional_env_var("OBS_DUCK_FADE_MS") {
+            Some(ms) => {
+                Duration::from_millis(ms.parse().context("failed to decode OBS_DUCK_FADE_MS")?)
+            }
+            None => DEFAULT_FADE,
+        };
+
+        Ok(Self {
+            mute,
+            duck,
+            duck_db,
+            fade,
+        })
+    }
+}
+
+const DEFAULT_INTRO: Duration = Duration::from_secs(5);
+
+/// scenes switched along with pages. nothing is switched for unset ones.
+pub(crate) struct SceneConfig {
+    waiting: Option<String>,
+    lt: Option<String>,
+    intro: Option<String>,
+    intro_duration: Duration,
+    transition: Option<String>,
+    transition_duration: Option<Duration>,
+}
+
+impl SceneConfig {
+    pub(crate) fn from_env() -> Result<Self> {
+        let millis = |name: &str| {
+            optional_env_var(name)
+                .map(|x| x.parse().map(Duration::from_millis))
+                .transpose()
+                .with_context(|| format!("failed to decode {}", name))
+        };
+
+        Ok(Self {
+            waiting: optional_env_var("OBS_WAITING_SCENE"),
+            lt: optional_env_var("OBS_LT_SCENE"),
+            intro: optional_env_var("OBS_INTRO_SCENE"),
+            intro_duration: millis("OBS_INTRO_MS")?.unwrap_or(DEFAULT_INTRO),
+            transition: optional_env_var("OBS_TRANSITION"),
+            transition_duration: millis("OBS_TRANSITION_MS")?,
+        })
+    }
+
+    fn scene_of(&self, page: Page) -> Option<&str> {
+        match page {
+            Page::WaitingScreen => self.waiting.as_deref(),
+            Page::LTScreen => self.lt.as_deref(),
+        }
+    }
+}
+
+/// the page on air, which the intro covers for a while.
+struct SceneState {
+    page: Page,
+    intro_until: Option<Instant>,
+}
+
+impl SceneState {
+    fn new() -> Self {
+        Self {
+            page: Page::LTScreen,
+            intro_until: None,
+        }
+    }
+
+    /// the scene to switch to for the action. other actions don't end the intro.
+    fn apply<'a>(
+        &mut self,
+        action: &ObsAction,
+        scenes: &'a SceneConfig,
+        now: Instant,
+    ) -> Option<&'a str> {
+        match action {
+            ObsAction::ShowPage(page) => {
+                self.page = *page;
+                self.intro_until = None;
+                scenes.scene_of(*page)
+            }
+
+            ObsAction::PresenterIntro => {
+                let intro = scenes.intro.as_deref()?;
+                self.intro_until = Some(now + scenes.intro_duration);
+                Some(intro)
+            }
+
+            ObsAction::TalkCancelled { .. } => {
+                self.intro_until.take()?;
+                scenes.scene_of(self.page)
+            }
+
+            _ => None,
+        }
+    }
+
+    /// the scene to go back to if the intro is over.
+    fn end_intro<'a>(&mut self, scenes: &'a SceneConfig, now: Instant) -> Option<&'a str> {
+        if self.intro_until? > now {
+            return None;
+        }
+
+        self.intro_until = None;
+        scenes.scene_of(self.page)
+    }
+}
+
+#[test]
+fn test_scene_state_on_pop() {
+    use crate::{
+        control::{execute, ControlCommand, PresentationCommand},
+        presentations::Presentations,
+        test_util::presentation,
+    };
+
+    let (tx, _screen) = tokio::sync::mpsc::channel(100);
+    let ctx = Context::for_test(Some(tx));
+
+    let actions = ctx.rt.block_on(async {
+        let (obs_tx, mut obs_rx) = tokio::sync::mpsc::channel(10);
+        *ctx.obs_chan.write().await = Some(obs_tx);
+
+        *ctx.presentations.write().await =
+            Presentations::from_vec(vec![presentation("alice", "限界LT", None)]);
+
+        execute(&ctx, ControlCommand::Presentation(PresentationCommand::Pop)).await;
+
+        let mut actions = vec![];
+        while let Ok(action) = obs_rx.try_recv() {
+            actions.push(action);
+        }
+        actions
+    });
+
+    assert!(matches!(
+        actions.as_slice(),
+        [ObsAction::PresenterIntro, ObsAction::TalkStarted { .. }]
+    ));
+
+    let scenes = SceneConfig {
+        waiting: Some("waiting".into()),
+        lt: Some("lt".into()),
+        intro: Some("intro".into()),
+        intro_duration: Duration::from_secs(5),
+        transition: None,
+        transition_duration: None,
+    };
+
+    let now = Instant::now();
+    let mut state = SceneState::new();
+
+    let switched = actions
+        .iter()
+        .filter_map(|x| state.apply(x, &scenes, now))
+        .collect::<Vec<_>>();
+
+    // the talk starting doesn't end the intro
+    assert_eq!(switched, ["intro"]);
+    assert_eq!(state.end_intro(&scenes, now + Duration::from_secs(1)), None);
+    assert_eq!(
+        state.end_intro(&scenes, now + Duration::from_secs(5)),
+        Some("lt")
+    );
+    assert_eq!(state.end_intro(&scenes, now + Duration::from_secs(6)), None);
+
+    // pausing during the intro goes to the waiting scene and ends it
+    state.apply(&ObsAction::PresenterIntro, &scenes, now);
+    let paused = state.apply(&ObsAction::ShowPage(Page::WaitingScreen), &scenes, now);
+    assert_eq!(paused, Some("waiting"));
+    assert_eq!(state.end_intro(&scenes, now + Duration::from_secs(5)), None);
+}
+
+#[test]
+fn test_scene_state_on_cancel() {
+    // the intro of the cancelled talk is cut short
+    let scenes = SceneConfig {
+        waiting: None,
+        lt: Some("lt".into()),
+        intro: Some("intro".into()),
+        intro_duration: Duration::from_secs(5),
+        transition: None,
+        transition_duration: None,
+    };
+
+    let now = Instant::now();
+    let mut state = SceneState::new();
+    state.apply(&ObsAction::PresenterIntro, &scenes, now);
+
+    let cancelled = ObsAction::TalkCancelled {
+        presenter: "bob".into(),
+        title: "bobのLT".into(),
+    };
+    assert_eq!(state.apply(&cancelled, &scenes, now), Some("lt"));
+    assert_eq!(state.apply(&cancelled, &scenes, now), None);
+}
+
+/// how the inputs were before muting, to be restored exactly
+#[derive(Default)]
+struct SavedAudio {
+    muted: Vec<(String, bool)>,
+    // (name, dB, mul)
+    volumes: Vec<(String, f32, f32)>,
+}
+
+/// volumes in dB to set one by one while fading
+fn fade_steps(from: f32, to: f32, steps: u32) -> Vec<f32> {
+    let (from, to) = (from.max(MIN_DB), to.max(MIN_DB));
+
+    (1..=steps)
+        .map(|i| from + (to - from) * i as f32 / steps as f32)
+        .collect()
+}
+
+#[test]
+fn test_fade_steps() {
+    assert_eq!(fade_steps(0.0, -20.0, 4), [-5.0, -10.0, -15.0, -20.0]);
+    assert_eq!(fade_steps(f32::NEG_INFINITY, 0.0, 2), [-50.0, 0.0]);
+    assert_eq!(fade_steps(-6.0, -6.0, 1), [-6.0]);
+}
+
+const RECONNECT_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(60);
+const HEALTH_INTERVAL: Duration = Duration::from_secs(5);
+// a request which takes longer means the connection is dead
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_SCREENSHOT_DIR: &str = "screenshots";
+// bitrate is measured over at least this
+const BITRATE_INTERVAL: Duration = Duration::from_secs(1);
+// actions kept while disconnected. older ones are dropped.
+const QUEUE_LIMIT: usize = 32;
+
+/// where OBS WebSocket listens
+pub(crate) struct ObsConfig {
+    address: String,
+    port: u16,
+    password: String,
+    audio: AudioConfig,
+    scenes: SceneConfig,
+    recording: RecordingConfig,
+    // on the machine running OBS
+    screenshot_dir: PathBuf,
+}
+
+impl ObsConfig {
+    pub(crate) fn from_env() -> Result<Self> {
+        Ok(Self {
+            address: env_var("OBS_ADDRESS"),
+            port: env_var("OBS_PORT")
+                .parse()
+                .context("failed to decode OBS_PORT")?,
+            password: env_var("OBS_PASS"),
+            audio: AudioConfig::from_env()?,
+            scenes: SceneConfig::from_env()?,
+            recording: RecordingConfig::from_env()?,
+            screenshot_dir: match optional_env_var("OBS_SCREENSHOT_DIR") {
+                Some(dir) => dir.into(),
+                // OBS requires an absolute path
+                None => std::env::current_dir()
+                    .context("failed to get the current directory")?
+                    .join(DEFAULT_SCREENSHOT_DIR),
+            },
+        })
+    }
+}
+
+/// how the stream is going, as shown to operators
+#[derive(Clone, Debug, PartialEq)]
+struct StreamHealth {
+    duration: Duration,
+    // None until measured
+    bitrate_kbps: Option<u64>,
+    skipped_frames: u32,
+    total_frames: u32,
+    reconnecting: bool,
+    // (bytes, duration) where the bitrate was measured last
+    sample: (u64, Duration),
+}
+
+impl StreamHealth {
+    /// None if not streaming. the bitrate is measured since the previous health.
+    fn of(previous: Option<&StreamHealth>, status: &StreamStatus) -> Option<Self> {
+        if !status.active {
+            return None;
+        }
+
+        let duration: Duration = status.duration.try_into().unwrap_or_default();
+        let sample = (status.bytes, duration);
+
+        let (bitrate_kbps, sample) = match previous {
+            // the stream was restarted if it went back
+            Some(previous)
+                if previous.sample.1 <= duration && previous.sample.0 <= status.bytes =>
+            {
+                let elapsed = duration - previous.sample.1;
+
+                if elapsed < BITRATE_INTERVAL {
+                    (previous.bitrate_kbps, previous.sample)
+                } else {
+                    let bits = (status.bytes - previous.sample.0) * 8;
+                    let kbps = bits as f64 / elapsed.as_secs_f64() / 1000.0;
+                    (Some(kbps.round() as u64), sample)
+                }
+            }
+
+            _ => (None, sample),
+        };
+
+        Some(Self {
+            duration,
+            bitrate_kbps,
+            skipped_frames: status.skipped_frames,
+            total_frames: status.total_frames,
+            reconnecting: status.reconnecting,
+            sample,
+        })
+    }
+}
+
+impl fmt::Display for StreamHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.duration.as_secs();
+        write!(
+            f,
+            "live for {}:{:02}:{:02}",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        )?;
+
+        match self.bitrate_kbps {
+            Some(kbps) => write!(f, ", {} kbps", kbps)?,
+            None => write!(f, ", measuring bitrate")?,
+        }
+
+        write!(
+            f,
+            ", {} of {} frames dropped ({:.2}%)",
+            self.skipped_frames,
+            self.total_frames,
+            self.skipped_frames as f64 * 100.0 / self.total_frames.max(1) as f64
+        )?;
+
+        if self.reconnecting {
+            write!(f, ", reconnecting")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_stream_health() {
+    let status = |secs: u64, bytes: u64| -> StreamStatus {
+        serde_json::from_value(serde_json::json!({
+            "outputActive": secs > 0,
+            "outputReconnecting": false,
+            "outputTimecode": "00:00:00.000",
+            "outputDuration": secs * 1000,
+            "outputCongestion": 0.0,
+            "outputBytes": bytes,
+            "outputSkippedFrames": 30,
+            "outputTotalFrames": 12000,
+        }))
+        .unwrap()
+    };
+
+    let first = StreamHealth::of(None, &status(10, 1_000_000)).unwrap();
+    assert_eq!(first.bitrate_kbps, None);
+
+    // 6 Mbit in 5 secs
+    let second = StreamHealth::of(Some(&first), &status(15, 1_750_000)).unwrap();
+    assert_eq!(second.bitrate_kbps, Some(1200));
+    assert_eq!(
+        second.to_string(),
+        "live for 0:00:15, 1200 kbps, 30 of 12000 frames dropped (0.25%)"
+    );
+
+    // too soon to measure again
+    let third = StreamHealth::of(Some(&second), &status(15, 1_800_000)).unwrap();
+    assert_eq!(third, second);
+
+    // restarted
+    let restarted = StreamHealth::of(Some(&second), &status(3, 100_000)).unwrap();
+    assert_eq!(restarted.bitrate_kbps, None);
+
+    assert!(StreamHealth::of(Some(&second), &status(0, 0)).is_none());
+}
+
+/// the connection to OBS as shown to operators
+#[derive(Default)]
+pub(crate) struct ObsStatus {
+    connected: bool,
+    // None if not streaming
+    stream: Option<StreamHealth>,
+    recording: bool,
+    recording_paused: bool,
+    disconnected_at: Option<Instant>,
+    // why the last connection attempt failed
+    last_error: Option<String>,
+}
+
+impl ObsStatus {
+    /// the stream and the recording
+    fn outputs(&self) -> String {
+        let stream = match &self.stream {
+            Some(health) => health.to_string(),
+            None => "off".into(),
+        };
+
+        let recording = match (self.recording, self.recording_paused) {
+            (true, true) => "paused",
+            (true, false) => "on",
+            (false, _) => "off",
+        };
+
+        format!("stream: {}\nrecording: {}", stream, recording)
+    }
+}
+
+impl fmt::Display for ObsStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.connected {
+            write!(f, "connected\n{}", self.outputs())
+        } else {
+            write!(f, "disconnected")?;
+
+            if let Some(at) = self.disconnected_at {
+                write!(f, " for {}s", at.elapsed().as_secs())?;
+            }
+
+            write!(f, ", reconnecting")?;
+
+            match &self.last_error {
+                Some(e) => write!(f, " (last error: {})", e),
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+/// keeps an action until OBS comes back.
+fn enqueue(queued: &mut VecDeque<ObsAction>, action: ObsAction) {
+    let action = match action {
+        // the intro is pointless once the talk has begun
+        ObsAction::PresenterIntro => {
+            tracing::info!("dropped the presenter intro while obs is disconnected");
+            return;
+        }
+
+        // operators should decide again rather than have it done at some point
+        ObsAction::Command { command, reply } => {
+            tracing::warn!("rejected {:?} while obs is disconnected", command);
+            reply
+                .send("obs is disconnected. try again later".into())
+                .ok();
+            return;
+        }
+
+        action => action,
+    };
+
+    if queued.len() >= QUEUE_LIMIT {
+        if let Some(dropped) = queued.pop_front() {
+            tracing::warn!(
+                "too many obs actions while disconnected. dropped {:?}",
+                dropped
+            );
+        }
+    }
+
+    queued.push_back(action);
+}
+
+#[test]
+fn test_enqueue() {
+    let mut queued = VecDeque::new();
+
+    enqueue(&mut queued, ObsAction::PresenterIntro);
+    assert!(queued.is_empty());
+
+    for _ in 0..QUEUE_LIMIT {
+        enqueue(&mut queued, ObsAction::Mute);
+    }
+    enqueue(&mut queued, ObsAction::UnMute);
+
+    assert_eq!(queued.len(), QUEUE_LIMIT);
+    assert!(matches!(queued.back(), Some(ObsAction::UnMute)));
+}
+
+/// retries with backoff until connected. actions sent meanwhile are queued.
+/// None if the sender has gone.
+async fn connect(
+    ctx: &Context,
+    config: &ObsConfig,
+    queued: &mut VecDeque<ObsAction>,
+    re: &mut Receiver<ObsAction>,
+) -> Option<Client> {
+    let mut backoff = RECONNECT_MIN;
+
+    loop {
+        match Client::connect(&config.address, config.port, Some(&config.password)).await {
+            Ok(client) => {
+                let mut status = ctx.obs_status.write().await;
+                status.connected = true;
+                status.disconnected_at = None;
+                status.last_error = None;
+
+                tracing::info!("connected to obs");
+                return Some(client);
+            }
+
+            Err(e) => {
+                tracing::warn!("failed to connect to obs. retrying in {:?}: {}", backoff, e);
+
+                let mut status = ctx.obs_status.write().await;
+                status.connected = false;
+                status.disconnected_at.get_or_insert_with(Instant::now);
+                status.last_error = Some(e.to_string());
+            }
+        }
+
+        let retry = tokio::time::sleep(backoff);
+        tokio::pin!(retry);
+
+        loop {
+            tokio::select! {
+                _ = &mut retry => break,
+
+                action = re.recv() => match action {
+                    Some(action) => enqueue(queued, action),
+                    None => return None,
+                },
+            }
+        }
+
+        backoff = (backoff * 2).min(RECONNECT_MAX);
+    }
+}
+
+/// applies actions to OBS, reconnecting whenever it goes away. returns when the sender has gone.
+pub(crate) async fn run(ctx: Arc<Context>, config: ObsConfig, mut re: Receiver<ObsAction>) {
+    let mut queued = VecDeque::new();
+
+    // talks recorded before restarting are kept
+    let talks = match TalkLog::load(config.recording.talks_file.clone()).await {
+        Ok(talks) => talks,
+        Err(e) => {
+            tracing::error!(
+                "failed to load talks. a new file is written instead: {:?}",
+                e
+            );
+            TalkLog::new(config.recording.talks_file.with_extension("new.json"))
+        }
+    };
+
+    let Some(client) = connect(&ctx, &config, &mut queued, &mut re).await else {
+        return;
+    };
+
+    let mut obs = ObsClient {
+        ctx: Arc::clone(&ctx),
+        client,
+        saved: None,
+        scene: SceneState::new(),
+        recording: config.recording.mode,
+        talks,
+        fps: 30,
+        queued,
+        config,
+    };
+
+    obs.fetch_fps().await;
+
+    let mut health = tokio::time::interval(HEALTH_INTERVAL);
+
+    loop {
+        let action = match obs.queued.pop_front() {
+            Some(action) => action,
+
+            None => tokio::select! {
+                action = re.recv() => match action {
+                    Some(action) => action,
+                    None => break,
+                },
+
+                _ = tokio::time::sleep_until(obs.scene.intro_until.unwrap_or_else(Instant::now)),
+                    if obs.scene.intro_until.is_some() =>
+                {
+                    let now = Instant::now();
+
+                    if let Some(scene) = obs.scene.end_intro(&obs.config.scenes, now) {
+                        obs.switch_scene(scene).await;
+                    }
+
+                    continue;
+                }
+
+                _ = health.tick() => {
+                    if let Err(e) = obs.check_health().await {
+                        if !obs.reconnect(&mut re, e).await {
+                            break;
+                        }
+                    }
+
+                    continue;
+                }
+            },
+        };
+
+        let retry = action.retry();
+        obs.apply(action).await;
+
+        // errors in the action may be from a lost connection. it's applied again after reconnecting then.
+        if let Err(e) = obs.check_health().await {
+            if let Some(action) = retry {
+                obs.queued.push_front(action);
+            }
+
+            if !obs.reconnect(&mut re, e).await {
+                break;
+            }
+        }
+    }
+}
+
+struct ObsClient {
+    ctx: Arc<Context>,
+    client: Client,
+    config: ObsConfig,
+    // sent while disconnected
+    queued: VecDeque<ObsAction>,
+    saved: Option<SavedAudio>,
+    scene: SceneState,
+    recording: RecordingMode,
+    talks: TalkLog,
+    // of the recording, for the EDL
+    fps: u32,
+}
+
+impl ObsClient {
+    /// false if the sender has gone while reconnecting.
+    async fn reconnect(&mut self, re: &mut Receiver<ObsAction>, e: anyhow::Error) -> bool {
+        self.ctx
+            .alerts
+            .send(format!("OBS disconnected: {}. reconnecting", e));
+
+        {
+            let mut status = self.ctx.obs_status.write().await;
+            status.connected = false;
+            status.disconnected_at = Some(Instant::now());
+        }
+
+        let Some(client) = connect(&self.ctx, &self.config, &mut self.queued, re).await else {
+            return false;
+        };
+
+        self.client = client;
+        self.fetch_fps().await;
+        self.restore().await;
+
+        self.ctx.alerts.send(format!(
+            "OBS reconnected. {} queued actions are applied",
+            self.queued.len()
+        ));
+
+        true
+    }
+
+    /// OBS may have been restarted while disconnected. puts back the scene and the audio we set.
+    async fn restore(&mut self) {
+        self.scene.intro_until = None;
+
+        if let Some(scene) = self.config.scenes.scene_of(self.scene.page) {
+            self.switch_scene(scene).await;
+        }
+
+        // anything saved on the LT screen is what failed to be unmuted. presenters must be heard.
+        if self.scene.page != Page::WaitingScreen {
+            self.unmute().await;
+            return;
+        }
+
+        let Some(saved) = &self.saved else {
+            return;
+        };
+
+        let inputs = self.client.inputs();
+
+        for (name, _) in &saved.muted {
+            if let Err(e) = inputs.set_muted(name, true).await {
+                tracing::warn!("failed to mute {} again: {}", name, e);
+            }
+        }
+
+        for (name, _, _) in &saved.volumes {
+            let db = Volume::Db(self.config.audio.duck_db);
+
+            if let Err(e) = inputs.set_volume(name, db).await {
+                tracing::warn!("failed to duck {} again: {}", name, e);
+            }
+        }
+    }
+
+    async fn fetch_fps(&mut self) {
+        self.fps = match self.client.config().video_settings().await {
+            Ok(x) => (x.fps_numerator as f64 / x.fps_denominator.max(1) as f64).round() as u32,
+            Err(e) => {
+                tracing::warn!("failed to get fps. 30 is used for EDL: {}", e);
+                30
+            }
+        }
+        .max(1);
+    }
+
+    /// fails if the connection is dead. alerts when streaming or recording stopped without us.
+    async fn check_health(&mut self) -> Result<()> {
+        let (stream, record) = tokio::time::timeout(HEALTH_TIMEOUT, async {
+            let stream = self.client.streaming().status().await?;
+            let record = self.client.recording().status().await?;
+            Ok::<_, obws::Error>((stream, record))
+        })
+        .await
+        .context("obs didn't respond")??;
+
+        let mut status = self.ctx.obs_status.write().await;
+
+        if status.stream.is_some() && !stream.active {
+            self.ctx.alerts.send("OBS streaming stopped unexpectedly");
+        }
+
+        if status.recording && !record.active {
+            self.ctx.alerts.send("OBS recording stopped unexpectedly");
+        }
+
+        // started in OBS directly. talks from now are in the new file.
+        if !status.recording && record.active {
+            self.talks.new_recording();
+        }
+
+        status.connected = true;
+        status.stream = StreamHealth::of(status.stream.as_ref(), &stream);
+        status.recording = record.active;
+        status.recording_paused = record.paused;
+
+        Ok(())
+    }
+
+    async fn apply(&mut self, action: ObsAction) {
+        let scene = self
+            .scene
+            .apply(&action, &self.config.scenes, Instant::now())
+            .map(str::to_owned);
+
+        match action {
+            ObsAction::Mute => {
+                if let Err(e) = self.mute().await {
+                    tracing::warn!("failed to mute: {:?}", e);
+                }
+            }
+
+            ObsAction::UnMute => self.unmute().await,
+
+            ObsAction::ShowPage(Page::WaitingScreen) => self.end_talk().await,
+
+            ObsAction::ShowPage(Page::LTScreen) | ObsAction::PresenterIntro => {}
+
+            ObsAction::TalkStarted { presenter, title } => {
+                self.start_talk(presenter, title).await;
+            }
+
+            ObsAction::TalkCancelled { presenter, title } => {
+                self.cancel_talk(&presenter, &title).await;
+            }
+
+            ObsAction::Command { command, reply } => {
+                let result = match self.command(command).await {
+                    Ok(result) => result,
+                    Err(e) => {
+                        tracing::warn!("obs command failed: {:?}", e);
+                        format!("failed: {}", e)
+                    }
+                };
+
+                if let Err(e) = self.check_health().await {
+                    tracing::warn!("failed to get the stream health: {:?}", e);
+                }
+
+                let outputs = self.ctx.obs_status.read().await.outputs();
+                reply.send(format!("{}\n{}", result, outputs)).ok();
+            }
+        }
+
+        if let Some(scene) = scene {
+            self.switch_scene(&scene).await;
+        }
+    }
+
+    async fn command(&mut self, command: ObsCommand) -> Result<String> {
+        match command {
+            ObsCommand::StreamStart => {
+                self.client.streaming().start().await?;
+                Ok("stream started".into())
+            }
+
+            ObsCommand::StreamStop { .. } => {
+                self.client.streaming().stop().await?;
+                // stopped on purpose. not reported as unexpected.
+                self.ctx.obs_status.write().await.stream = None;
+                Ok("stream stopped".into())
+            }
+
+            ObsCommand::RecordStart => {
+                self.client.recording().start().await?;
+                self.talks.new_recording();
+                self.ctx.obs_status.write().await.recording = true;
+                Ok("recording started".into())
+            }
+
+            ObsCommand::RecordStop { .. } => {
+                // the talk ends with the recording. it's stopped here in split mode.
+                self.end_talk().await;
+
+                if !self.client.recording().status().await?.active {
+                    return Ok("recording stopped".into());
+                }
+
+                let path = self.client.recording().stop().await?;
+                self.ctx.obs_status.write().await.recording = false;
+
+                // talks in chapters mode are known to be in the file only now
+                if self.recording == RecordingMode::Chapters {
+                    self.talks.recorded(&path);
+                    self.save_talks().await;
+                }
+
+                Ok(format!("recording stopped: {}", path))
+            }
+
+            ObsCommand::RecordPause => {
+                self.client.recording().pause().await?;
+                Ok("recording paused".into())
+            }
+
+            ObsCommand::RecordResume => {
+                self.client.recording().resume().await?;
+                Ok("recording resumed".into())
+            }
+
+            ObsCommand::ReplaySave => {
+                let replay = self.client.replay_buffer();
+                replay.save().await?;
+
+                // the file may not be written yet
+                match replay.last_replay().await {
+                    Ok(path) => Ok(format!("replay saved: {}", path)),
+                    Err(_) => Ok("replay saved".into()),
+                }
+            }
+
+            ObsCommand::Screenshot { scene } => {
+                let secs = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+
+                let path = self.config.screenshot_dir.join(format!(
+                    "{} {}.png",
+                    sanitize_file_name(&scene),
+                    secs
+                ));
+
+                // fails if OBS runs on another machine, where the directory has to exist
+                tokio::fs::create_dir_all(&self.config.screenshot_dir)
+                    .await
+                    .ok();
+
+                self.client
+                    .sources()
+                    .save_screenshot(SaveScreenshot {
+                        source: &scene,
+                        format: "png",
+                        width: None,
+                        height: None,
+                        compression_quality: None,
+                        file_path: &path,
+                    })
+                    .await?;
+
+                Ok(format!("screenshot saved: {}", path.display()))
+            }
+        }
+    }
+
+    async fn start_talk(&mut self, presenter: String, title: String) {
+        if self.recording == RecordingMode::Off {
+            return;
+        }
+
+        // applied again after reconnecting though it had been done
+        if self
+            .talks
+            .ongoing()
+            .is_some_and(|x| x.presenter() == presenter && x.title() == title)
+        {
+            return;
+        }
+
+        // popped without pausing
+        if self.recording == RecordingMode::Split {
+            self.end_talk().await;
+        }
+
+        let recording = self.client.recording();
+
+        let mut status = match recording.status().await {
+            Ok(status) => status,
+            Err(e) => {
+                tracing::warn!("failed to get recording status: {}", e);
+                return;
+            }
+        };
+
+        // each talk gets a fresh file even if something is being recorded
+        if self.recording == RecordingMode::Split && status.active {
+            match recording.stop().await {
+                Ok(path) => {
+                    tracing::info!("stopped recording {} to start a talk", path);
+                    status.active = false;
+                    // stopped on purpose. not reported as unexpected.
+                    self.ctx.obs_status.write().await.recording = false;
+                }
+                Err(e) => tracing::warn!("failed to stop recording: {}", e),
+            }
+        }
+
+        let offset = if status.active {
+            status.duration.try_into().unwrap_or_default()
+        } else {
+            match recording.start().await {
+                Ok(()) => {
+                    self.talks.new_recording();
+                    self.ctx.obs_status.write().await.recording = true;
+                    Duration::ZERO
+                }
+                Err(e) => {
+                    tracing::warn!("failed to start recording: {}", e);
+                    return;
+                }
+            }
+        };
+
+        tracing::info!("recording {} - {}", presenter, title);
+        self.talks.start(presenter, title, offset);
+        self.save_talks().await;
+    }
+
+    async fn end_talk(&mut self) {
+        if self.recording == RecordingMode::Off || !self.talks.is_ongoing() {
+            return;
+        }
+
+        let recording = self.client.recording();
+
+        let offset = match recording.status().await {
+            Ok(status) => status.duration.try_into().unwrap_or_default(),
+            Err(e) => {
+                tracing::warn!("failed to get recording status: {}", e);
+                return;
+            }
+        };
+
+        let Some(talk) = self.talks.end(offset) else {
+            return;
+        };
+
+        if self.recording == RecordingMode::Split {
+            match recording.stop().await {
+                Ok(path) => {
+                    // stopped on purpose. not reported as unexpected.
+                    self.ctx.obs_status.write().await.recording = false;
+                    let path = PathBuf::from(path);
+                    let renamed =
+                        talk_file_path(&path, talk.index(), talk.presenter(), talk.title());
+
+                    // fails if OBS runs on another machine. the original name is kept then.
+                    talk.file = Some(match tokio::fs::rename(&path, &renamed).await {
+                        Ok(()) => renamed.display().to_string(),
+                        Err(e) => {
+                            tracing::warn!("failed to rename {}: {}", path.display(), e);
+                            path.display().to_string()
+                        }
+                    });
+                }
+
+                Err(e) => tracing::warn!("failed to stop recording: {}", e),
+            }
+        }
+
+        self.save_talks().await;
+    }
+
+    async fn cancel_talk(&mut self, presenter: &str, title: &str) {
+        // applied again after reconnecting though it had been done, or not recorded at all
+        if self.recording == RecordingMode::Off || !self.talks.cancel(presenter, title) {
+            return;
+        }
+
+        tracing::info!("cancelled recording {} - {}", presenter, title);
+
+        // the file is left as OBS named it
+        if self.recording == RecordingMode::Split {
+            match self.client.recording().stop().await {
+                Ok(path) => {
+                    // stopped on purpose. not reported as unexpected.
+                    self.ctx.obs_status.write().await.recording = false;
+                    tracing::info!("stopped recording {} of the cancelled talk", path);
+                }
+
+                Err(e) => tracing::warn!("failed to stop recording: {}", e),
+            }
+        }
+
+        self.save_talks().await;
+    }
+
+    async fn save_talks(&self) {
+        if let Err(e) = self.talks.save(self.fps).await {
+            tracing::warn!("failed to save talks: {:?}", e);
+        }
+    }
+
+    async fn switch_scene(&self, scene: &str) {
+        let result = async {
+            let transitions = self.client.transitions();
+
+            if let Some(transition) = &self.config.scenes.transition {
+                transitions.set_current(transition).await?;
+            }
+
+            if let Some(duration) = self.config.scenes.transition_duration {
+                transitions
+                    .set_current_duration(duration.try_into().unwrap_or_default())
+                    .await?;
+            }
+
+            self.client.scenes().set_current_program_scene(scene).await
+        }
+        .await;
+
+        match result {
+            Ok(()) => tracing::info!("switched obs scene to {}", scene),
+            Err(e) => tracing::warn!("failed to switch obs scene to {}: {}", scene, e),
+        }
+    }
+
+    async fn mute(&mut self) -> Result<()> {
+        let inputs = self.client.inputs();
+
+        let mute_targets = match &self.config.audio.mute {
+            MuteTargets::All => inputs
+                .list(None)
+                .await
+                .context("failed to fetch sources")?
+                .into_iter()
+                .map(|x| x.name)
+                .filter(|x| !self.config.audio.duck.contains(x))
+                .collect(),
+
+            MuteTargets::Only(names) => names.clone(),
+        };
+
+        // inputs already muted keep the state before the first mute.
+        // the rest of a mute cut short by a disconnection is done when it's applied again.
+        let mut saved = self.saved.take().unwrap_or_default();
+        let mut fades = vec![];
+
+        for name in mute_targets {
+            if saved.muted.iter().any(|(x, _)| *x == name) {
+                continue;
+            }
+
+            let result = async {
+                let muted = inputs.muted(&name).await?;
+                inputs.set_muted(&name, true).await?;
+                Ok::<_, obws::Error>(muted)
+            }
+            .await;
+
+            match result {
+                Ok(muted) => saved.muted.push((name, muted)),
+                Err(e) => tracing::warn!("failed to mute {}: {}", name, e),
+            }
+        }
+
+        for name in &self.config.audio.duck {
+            if saved.volumes.iter().any(|(x, _, _)| x == name) {
+                continue;
+            }
+
+            match inputs.volume(name).await {
+                Ok(volume) => {
+                    saved.volumes.push((name.clone(), volume.db, volume.mul));
+                    fades.push((name.as_str(), volume.db, self.config.audio.duck_db));
+                }
+
+                Err(e) => tracing::warn!("failed to get the volume of {}: {}", name, e),
+            }
+        }
+
+        self.fade(&fades).await;
+        self.saved = Some(saved);
+
+        Ok(())
+    }
+
+    async fn unmute(&mut self) {
+        // not muted by us
+        let Some(saved) = self.saved.take() else {
+            return;
+        };
+
+        let inputs = self.client.inputs();
+
+        // restored again next time, e.g. after reconnecting
+        let mut failed = SavedAudio::default();
+
+        for (name, muted) in saved.muted {
+            if let Err(e) = inputs.set_muted(&name, muted).await {
+                tracing::warn!("failed to restore mute of {}: {}", name, e);
+                failed.muted.push((name, muted));
+            }
+        }
+
+        let fades = saved
+            .volumes
+            .iter()
+            .map(|(name, db, _)| (name.as_str(), self.config.audio.duck_db, *db))
+            .collect::<Vec<_>>();
+
+        self.fade(&fades).await;
+
+        // dB loses precision, so the exact multiplier is set at last
+        for (name, db, mul) in saved.volumes {
+            if let Err(e) = inputs.set_volume(&name, Volume::Mul(mul)).await {
+                tracing::warn!("failed to restore the volume of {}: {}", name, e);
+                failed.volumes.push((name, db, mul));
+            }
+        }
+
+        if !failed.muted.is_empty() || !failed.volumes.is_empty() {
+            self.saved = Some(failed);
+        }
+    }
+
+    /// changes volumes of (name, from dB, to dB) gradually at once.
+    async fn fade(&self, fades: &[(&str, f32, f32)]) {
+        if fades.is_empty() {
+            return;
+        }
+
+        let steps = (self.config.audio.fade.as_millis() / FADE_INTERVAL.as_millis()).max(1) as u32;
+
+        let fades = fades
+            .iter()
+            .map(|(name, from, to)| (*name, fade_steps(*from, *to, steps)))
+            .collect::<Vec<_>>();
+
+        for i in 0..steps as usize {
+            for (name, steps) in &fades {
+                if let Err(e) = self
+                    .client
+                    .inputs()
+                    .set_volume(name, Volume::Db(steps[i]))
+                    .await
+                {
+                    tracing::warn!("failed to set the volume of {}: {}", name, e);
+                }
+            }
+
+            if i + 1 < steps as usize {
+                tokio::time::sleep(FADE_INTERVAL).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use {
+    crate::{optional_env_var, state_file::write_atomically},
+    anyhow::{bail, Context as _, Result},
+    serde::{Deserialize, Serialize},
+    std::{
+        fmt::Write as _,
+        path::{Path, PathBuf},
+        time::Duration,
+    },
+    tokio::fs,
+};
+
+const DEFAULT_TALKS_FILE: &str = "./talks.json";
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum RecordingMode {
+    Off,
+    /// one file per talk, recorded from pop to pause
+    Split,
+    /// one continuous recording with the offsets of talks
+    Chapters,
+}
+
+pub(crate) struct RecordingConfig {
+    pub(super) mode: RecordingMode,
+    /// where talks are written. an EDL is written next to it.
+    pub(super) talks_file: PathBuf,
+}
+
+impl RecordingConfig {
+    pub(crate) fn from_env() -> Result<Self> {
+        let mode = match optional_env_var("OBS_RECORDING_MODE").as_deref() {
+            None | Some("off") => RecordingMode::Off,
+            Some("split") => RecordingMode::Split,
+            Some("chapters") => RecordingMode::Chapters,
+            Some(x) => bail!(
+                "unknown OBS_RECORDING_MODE {}. use off, split or chapters",
+                x
+            ),
+        };
+
+        let talks_file = optional_env_var("OBS_TALKS_FILE")
+            .unwrap_or_else(|| DEFAULT_TALKS_FILE.to_string())
+            .into();
+
+        Ok(Self { mode, talks_file })
+    }
+}
+
+/// a talk in the recording. offsets are from the start of the recording, which is `file` if known.
+#[derive(Serialize, Deserialize)]
+pub(super) struct Talk {
+    index: usize,
+    presenter: String,
+    title: String,
+    /// counted up for each recording. talks with the same number are in the same file.
+    #[serde(default)]
+    recording: u32,
+    pub(super) file: Option<String>,
+    start_secs: f64,
+    end_secs: Option<f64>,
+}
+
+impl Talk {
+    pub(super) fn index(&self) -> usize {
+        self.index
+    }
+
+    pub(super) fn presenter(&self) -> &str {
+        &self.presenter
+    }
+
+    pub(super) fn title(&self) -> &str {
+        &self.title
+    }
+}
+
+/// talks recorded so far, saved as JSON and EDL for editing afterwards.
+pub(super) struct TalkLog {
+    path: PathBuf,
+    talks: Vec<Talk>,
+    // talks before this were loaded from an earlier launch. they can't be ended anymore.
+    session_start: usize,
+    // of the talks starting from now
+    recording: u32,
+}
+
+impl TalkLog {
+    pub(super) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            talks: vec![],
+            session_start: 0,
+            recording: 0,
+        }
+    }
+
+    /// continues the file written before restarting, if any.
+    pub(super) async fn load(path: PathBuf) -> Result<Self> {
+        let json = match fs::read(&path).await {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::new(path)),
+            Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+        };
+
+        let talks: Vec<Talk> = serde_json::from_slice(&json)
+            .with_context(|| format!("failed to deserialize {}", path.display()))?;
+
+        // OBS may have kept recording, but it can't be told. talks from now go to another reel.
+        let recording = talks.iter().map(|x| x.recording + 1).max().unwrap_or(0);
+
+        Ok(Self {
+            path,
+            session_start: talks.len(),
+            talks,
+            recording,
+        })
+    }
+
+    /// talks from now are in a recording other than the earlier ones.
+    pub(super) fn new_recording(&mut self) {
+        if self
+            .talks
+            .last()
+            .is_some_and(|x| x.recording == self.recording)
+        {
+            self.recording += 1;
+        }
+    }
+
+    /// the recording of the talks so far has been written to `file`.
+    pub(super) fn recorded(&mut self, file: &str) {
+        for talk in &mut self.talks[self.session_start..] {
+            if talk.recording == self.recording && talk.file.is_none() {
+                talk.file = Some(file.to_string());
+            }
+        }
+
+        self.new_recording();
+    }
+
+    /// ends the previous talk at the offset if it's still ongoing.
+    pub(super) fn start(&mut self, presenter: String, title: String, offset: Duration) {
+        self.end(offset);
+
+        self.talks.push(Talk {
+            index: self.talks.len() + 1,
+            presenter,
+            title,
+            recording: self.recording,
+            file: None,
+            start_secs: offset.as_secs_f64(),
+            end_secs: None,
+        });
+    }
+
+    /// the talk which has just ended, if any.
+    pub(super) fn end(&mut self, offset: Duration) -> Option<&mut Talk> {
+        let talk = self.talks[self.session_start..]
+            .last_mut()
+            .filter(|x| x.end_secs.is_none())?;
+        talk.end_secs = Some(offset.as_secs_f64());
+        Some(talk)
+    }
+
+    /// forgets the ongoing talk if it's the one given. the talk it ended goes on again if it's
+    /// in the same recording, i.e. the talks were not split into files.
+    pub(super) fn cancel(&mut self, presenter: &str, title: &str) -> bool {
+        if !self
+            .ongoing()
+            .is_some_and(|x| x.presenter == presenter && x.title == title)
+        {
+            return false;
+        }
+
+        let cancelled = self.talks.pop().unwrap();
+
+        if let Some(previous) = self.talks[self.session_start..].last_mut() {
+            if previous.file.is_none() && previous.end_secs == Some(cancelled.start_secs) {
+                previous.end_secs = None;
+            }
+        }
+
+        true
+    }
+
+    pub(super) fn ongoing(&self) -> Option<&Talk> {
+        self.talks[self.session_start..]
+            .last()
+            .filter(|x| x.end_secs.is_none())
+    }
+
+    pub(super) fn is_ongoing(&self) -> bool {
+        self.ongoing().is_some()
+    }
+
+    /// CMX 3600. talks are placed one after another on the record side.
+    /// each recording is a reel, and audio is taken along with video.
+    fn edl(&self, fps: u32) -> String {
+        let mut edl = String::from("TITLE: LT\nFCM: NON-DROP FRAME\n");
+        let mut record = 0.0;
+
+        for talk in &self.talks {
+            let Some(end) = talk.end_secs else {
+                continue;
+            };
+
+            let length = end - talk.start_secs;
+
+            writeln!(
+                edl,
+                "\n{:03}  {:<8} B     C        {} {} {} {}",
+                talk.index,
+                format!("REC{:03}", talk.recording),
+                timecode(talk.start_secs, fps),
+                timecode(end, fps),
+                timecode(record, fps),
+                timecode(record + length, fps),
+            )
+            .unwrap();
+
+            writeln!(edl, "* FROM CLIP NAME: {} - {}", talk.presenter, talk.title).unwrap();
+
+            if let Some(file) = &talk.file {
+                writeln!(edl, "* SOURCE FILE: {}", file).unwrap();
+            }
+
+            record += length;
+        }
+
+        edl
+    }
+
+    pub(super) async fn save(&self, fps: u32) -> Result<()> {
+        let json = serde_json::to_vec_pretty(&self.talks).context("failed to serialize talks")?;
+
+        write_atomically(&self.path, &json).await?;
+        write_atomically(&self.path.with_extension("edl"), self.edl(fps).as_bytes()).await
+    }
+}
+
+fn timecode(secs: f64, fps: u32) -> String {
+    let frames = (secs * fps as f64).round() as u64;
+    let fps = fps as u64;
+    let secs = frames / fps;
+
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        frames % fps
+    )
+}
+
+/// replaces characters which can't be in file names on some platforms.
+pub(super) fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// "01 presenter - title.mkv" next to the recorded file
+pub(super) fn talk_file_path(
+    recorded: &Path,
+    index: usize,
+    presenter: &str,
+    title: &str,
+) -> PathBuf {
+    let name = sanitize_file_name(&format!("{:02} {} - {}", index, presenter, title));
+
+    let mut path = recorded.with_file_name(name.trim());
+
+    if let Some(ext) = recorded.extension() {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".");
+        name.push(ext);
+        path.set_file_name(name);
+    }
+
+    path
+}
+
+#[tokio::test]
+async fn test_talk_log_load() {
+    let path = std::env::temp_dir().join("sugoi-test-talks.json");
+    fs::remove_file(&path).await.ok();
+    assert!(TalkLog::load(path.clone()).await.unwrap().talks.is_empty());
+
+    let mut log = TalkLog::new(path.clone());
+    log.start("alice".into(), "first".into(), Duration::from_secs(10));
+    log.end(Duration::from_secs(300));
+    // the app crashed during this one
+    log.start("bob".into(), "second".into(), Duration::from_secs(400));
+    log.save(30).await.unwrap();
+
+    let mut log = TalkLog::load(path.clone()).await.unwrap();
+    assert_eq!(log.talks.len(), 2);
+    assert!(!log.is_ongoing());
+    assert!(log.end(Duration::from_secs(5)).is_none());
+
+    log.start("carol".into(), "third".into(), Duration::ZERO);
+    assert_eq!(log.talks[2].index(), 3);
+    // can't be told if it's in the recording before restarting
+    assert_eq!(log.talks[2].recording, 1);
+    assert!(log.is_ongoing());
+}
+
+#[test]
+fn test_talk_log() {
+    let mut log = TalkLog::new("talks.json".into());
+
+    log.start("alice".into(), "first".into(), Duration::from_secs(10));
+    assert!(log.is_ongoing());
+
+    // popped again without pausing
+    log.start(
+        "bob".into(),
+        "second".into(),
+        Duration::from_millis(310_500),
+    );
+    log.end(Duration::from_secs(600)).unwrap();
+    assert!(!log.is_ongoing());
+    assert!(log.end(Duration::from_secs(700)).is_none());
+
+    // recording stopped and started again
+    log.recorded("/rec/2024-01-01 10-00-00.mkv");
+    log.start("carol".into(), "third".into(), Duration::from_secs(5));
+    log.end(Duration::from_secs(65)).unwrap();
+
+    assert_eq!(
+        log.edl(30),
+        "TITLE: LT\nFCM: NON-DROP FRAME\n\
+         \n001  REC000   B     C        00:00:10:00 00:05:10:15 00:00:00:00 00:05:00:15\
+         \n* FROM CLIP NAME: alice - first\
+         \n* SOURCE FILE: /rec/2024-01-01 10-00-00.mkv\
+         \n\n002  REC000   B     C        00:05:10:15 00:10:00:00 00:05:00:15 00:09:50:00\
+         \n* FROM CLIP NAME: bob - second\
+         \n* SOURCE FILE: /rec/2024-01-01 10-00-00.mkv\
+         \n\n003  REC001   B     C        00:00:05:00 00:01:05:00 00:09:50:00 00:10:50:00\
+         \n* FROM CLIP NAME: carol - third\n"
+    );
+
+    // undone right after popping eve
+    log.start("dave".into(), "fourth".into(), Duration::from_secs(700));
+    log.start("eve".into(), "fifth".into(), Duration::from_secs(900));
+    assert!(!log.cancel("dave", "fourth"));
+    assert!(log.cancel("eve", "fifth"));
+    assert_eq!(log.ongoing().unwrap().title(), "fourth");
+    assert_eq!(log.talks.len(), 4);
+
+    assert_eq!(
+        talk_file_path(
+            Path::new("/rec/2024-01-01 10-00-00.mkv"),
+            3,
+            "a/b",
+            "what? \"LT\""
+        ),
+        Path::new("/rec/03 a_b - what_ _LT_.mkv")
+    );
+}
@@ -0,0 +1,32 @@
+use {
+    parking_lot::Mutex,
+    tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+};
+
+/// things to be shown to operators by a single client, e.g. Discord.
+/// items sent before the client takes the receiver are kept until then.
+pub(crate) struct Outbox<T> {
+    sender: UnboundedSender<T>,
+    receiver: Mutex<Option<UnboundedReceiver<T>>>,
+}
+
+impl<T> Outbox<T> {
+    pub(crate) fn new() -> Self {
+        let (sender, receiver) = unbounded_channel();
+
+        Self {
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    }
+
+    pub(crate) fn send(&self, item: T) {
+        // dropped if the client has gone away
+        self.sender.send(item).ok();
+    }
+
+    /// can be called only once. returns None afterwards.
+    pub(crate) fn take_receiver(&self) -> Option<UnboundedReceiver<T>> {
+        self.receiver.lock().take()
+    }
+}
@@ -2,17 +2,29 @@ use std::iter::FromIterator;
 
 use {
     crate::model::User,
-    anyhow::{Context as _, Result},
+    anyhow::{ensure, Context as _, Result},
     serde::{Deserialize, Serialize},
     serde_json::Value,
-    std::{collections::VecDeque, path::Path},
+    std::{collections::VecDeque, path::Path, time::Duration},
     tokio::fs,
 };
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub(crate) struct Presentation {
     pub(crate) presenter: User,
     pub(crate) title: String,
+
+    /// overrides default duration of the presentation if present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) duration_secs: Option<u64>,
+}
+
+impl Presentation {
+    pub(crate) fn duration(&self, default: Duration) -> Duration {
+        self.duration_secs
+            .map(Duration::from_secs)
+            .unwrap_or(default)
+    }
 }
 
 pub(crate) struct Presentations {
@@ -26,14 +38,16 @@ impl Presentations {
         }
     }
 
-    pub(crate) async fn save(&self, path: &Path) -> Result<()> {
-        let yaml = serde_yaml::to_string(&self.list).context("failed to serialize list")?;
+    pub(crate) fn from_vec(list: Vec<Presentation>) -> Self {
+        Self { list: list.into() }
+    }
 
-        fs::write(path, &yaml)
-            .await
-            .context("failed to write file")?;
+    pub(crate) fn len(&self) -> usize {
+        self.list.len()
+    }
 
-        Ok(())
+    pub(crate) fn to_vec(&self) -> Vec<Presentation> {
+        self.list.iter().cloned().collect()
     }
 
     pub(crate) async fn load_from_file(path: &Path) -> Result<Self> {
@@ -56,40 +70,58 @@ impl Presentations {
             .join("\n")
     }
 
-    pub(crate) async fn pop(&mut self) -> Option<Presentation> {
-        let result = self.list.pop_front();
-
-        if result.is_some() {
-            self.save(Path::new("./temp_presentations.yaml"))
-                .await
-                .unwrap();
-        }
+    pub(crate) fn pop(&mut self) -> Option<Presentation> {
+        self.list.pop_front()
+    }
 
-        result
+    pub(crate) fn remove(&mut self, index: usize) -> bool {
+        self.list.remove(index).is_some()
     }
 
-    pub(crate) async fn remove(&mut self, index: usize) -> bool {
-        let result = self.list.remove(index).is_some();
+    /// reorders the queue so that entries at `map` come first in the given order.
+    /// remaining entries keep their relative order after them,
+    /// so `[3, 0]` means "move 3 to the front, then 0".
+    pub(crate) fn reorder(&mut self, map: &[usize]) -> Result<()> {
+        ensure!(!map.is_empty(), "reorder requires at least 1 index");
+
+        let mut seen = vec![false; self.list.len()];
 
-        if result {
-            self.save(Path::new("./temp_presentations.yaml"))
-                .await
-                .unwrap();
+        for &index in map {
+            ensure!(
+                index < self.list.len(),
+                "index {} is out of range (queue has {} entries)",
+                index,
+                self.list.len()
+            );
+
+            ensure!(!seen[index], "index {} appears more than once", index);
+            seen[index] = true;
         }
 
-        result
+        let mut taken = self.list.drain(..).map(Some).collect::<Vec<_>>();
+
+        let mut list = map
+            .iter()
+            .map(|&i| taken[i].take().unwrap())
+            .collect::<VecDeque<_>>();
+
+        list.extend(taken.into_iter().flatten());
+
+        self.list = list;
+
+        Ok(())
     }
 
     pub(crate) fn get_mut(&mut self, index: usize) -> Option<&mut Presentation> {
         self.list.get_mut(index)
     }
 
-    pub(crate) async fn push(&mut self, p: Presentation) {
+    pub(crate) fn push(&mut self, p: Presentation) {
         self.list.push_back(p);
+    }
 
-        self.save(Path::new("./temp_presentations.yaml"))
-            .await
-            .unwrap();
+    pub(crate) fn push_front(&mut self, p: Presentation) {
+        self.list.push_front(p);
     }
 
     pub(crate) fn to_json_value(&self) -> Value {
@@ -100,3 +132,35 @@ impl Presentations {
         )
     }
 }
+
+#[test]
+fn test_reorder() {
+    fn titles(p: &Presentations) -> Vec<&str> {
+        p.list.iter().map(|x| x.title.as_str()).collect()
+    }
+
+    let mut p = Presentations::new();
+
+    for title in ["a", "b", "c", "d"] {
+        p.list.push_back(Presentation {
+            presenter: User {
+                icon: None,
+                ident: None,
+                name: "name".to_string(),
+            },
+            title: title.to_string(),
+            duration_secs: None,
+        });
+    }
+
+    p.reorder(&[3, 0]).unwrap();
+    assert_eq!(titles(&p), ["d", "a", "b", "c"]);
+
+    p.reorder(&[3, 2, 1, 0]).unwrap();
+    assert_eq!(titles(&p), ["c", "b", "a", "d"]);
+
+    assert!(p.reorder(&[]).is_err());
+    assert!(p.reorder(&[4]).is_err());
+    assert!(p.reorder(&[1, 1]).is_err());
+    assert_eq!(titles(&p), ["c", "b", "a", "d"]);
+}
@@ -0,0 +1,168 @@
+use {
+    super::SocialPoster,
+    crate::{env_var, optional_env_var},
+    anyhow::{Context as _, Result},
+    async_trait::async_trait,
+    serde::{de::DeserializeOwned, Deserialize},
+    serde_json::{json, Value},
+};
+
+const DEFAULT_PDS_URL: &str = "https://bsky.social";
+
+// Bluesky counts graphemes. chars are close enough for our messages.
+const MAX_LENGTH: usize = 300;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Session {
+    access_jwt: String,
+    did: String,
+}
+
+#[derive(Deserialize)]
+struct CreatedRecord {
+    // at://<did>/app.bsky.feed.post/<rkey>
+    uri: String,
+}
+
+/// posts to Bluesky through AT Protocol `com.atproto.repo.createRecord`.
+pub(crate) struct BlueskyClient {
+    http: reqwest::Client,
+    pds_url: String,
+    identifier: String,
+    app_password: String,
+}
+
+impl BlueskyClient {
+    pub(crate) fn new(pds_url: String, identifier: String, app_password: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            pds_url: pds_url.trim_end_matches('/').to_string(),
+            identifier,
+            app_password,
+        }
+    }
+
+    /// enabled when `BLUESKY_IDENTIFIER` is set.
+    pub(crate) fn from_env() -> Option<Self> {
+        let identifier = optional_env_var("BLUESKY_IDENTIFIER")?;
+
+        let pds_url =
+            optional_env_var("BLUESKY_PDS_URL").unwrap_or_else(|| DEFAULT_PDS_URL.to_string());
+
+        Some(Self::new(
+            pds_url,
+            identifier,
+            env_var("BLUESKY_APP_PASSWORD"),
+        ))
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        token: Option<&str>,
+        body: Value,
+    ) -> Result<T> {
+        let mut request = self
+            .http
+            .post(format!("{}/xrpc/{}", self.pds_url, method))
+            .json(&body);
+
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await.context("failed to send request")?;
+        let status = response.status();
+
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("{} returned {}: {}", method, status, body);
+        }
+
+        response.json().await.context("failed to decode response")
+    }
+}
+
+#[async_trait]
+impl SocialPoster for BlueskyClient {
+    fn name(&self) -> &'static str {
+        "bluesky"
+    }
+
+    fn max_length(&self) -> usize {
+        MAX_LENGTH
+    }
+
+    async fn post(&self, text: &str) -> Result<String> {
+        // posts are rare enough to log in every time rather than refreshing tokens
+        let session = self
+            .call::<Session>(
+                "com.atproto.server.createSession",
+                None,
+                json!({
+                    "identifier": self.identifier,
+                    "password": self.app_password,
+                }),
+            )
+            .await
+            .context("failed to create session")?;
+
+        let record = self
+            .call::<CreatedRecord>(
+                "com.atproto.repo.createRecord",
+                Some(&session.access_jwt),
+                json!({
+                    "repo": session.did,
+                    "collection": "app.bsky.feed.post",
+                    "record": {
+                        "$type": "app.bsky.feed.post",
+                        "text": text,
+                        "createdAt": chrono::Utc::now()
+                            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                    },
+                }),
+            )
+            .await
+            .context("failed to create record")?;
+
+        let rkey = record
+            .uri
+            .rsplit('/')
+            .next()
+            .context("record uri was empty")?;
+
+        Ok(format!(
+            "https://bsky.app/profile/{}/post/{}",
+            session.did, rkey
+        ))
+    }
+}
+
+#[tokio::test]
+async fn test_post() {
+    let (base, server) = crate::test_util::mock_http_server(vec![
+        (
+            200,
+            r#"{"accessJwt":"jwt","refreshJwt":"refresh","handle":"lt.bsky.social","did":"did:plc:lt"}"#,
+        ),
+        (
+            200,
+            r#"{"uri":"at://did:plc:lt/app.bsky.feed.post/3kabc","cid":"cid"}"#,
+        ),
+    ])
+    .await;
+
+    let client = BlueskyClient::new(base, "lt.bsky.social".into(), "password".into());
+
+    let url = client.post("hello").await.unwrap();
+    assert_eq!(url, "https://bsky.app/profile/did:plc:lt/post/3kabc");
+
+    let requests = server.await.unwrap();
+    assert!(requests[0].starts_with("POST /xrpc/com.atproto.server.createSession "));
+    assert!(requests[1].starts_with("POST /xrpc/com.atproto.repo.createRecord "));
+    assert!(requests[1]
+        .to_lowercase()
+        .contains("authorization: bearer jwt"));
+    assert!(requests[1].contains(r#""text":"hello""#));
+}
@@ -0,0 +1,109 @@
+use {
+    super::SocialPoster,
+    crate::{env_var, optional_env_var},
+    anyhow::{Context as _, Result},
+    async_trait::async_trait,
+    serde::Deserialize,
+    serde_json::json,
+};
+
+// default of vanilla Mastodon. some instances allow more.
+const DEFAULT_MAX_LENGTH: usize = 500;
+
+#[derive(Deserialize)]
+struct Status {
+    url: String,
+}
+
+/// posts statuses through `POST /api/v1/statuses`.
+pub(crate) struct MastodonClient {
+    http: reqwest::Client,
+    instance_url: String,
+    access_token: String,
+    max_length: usize,
+}
+
+impl MastodonClient {
+    pub(crate) fn new(instance_url: String, access_token: String, max_length: usize) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            instance_url: instance_url.trim_end_matches('/').to_string(),
+            access_token,
+            max_length,
+        }
+    }
+
+    /// enabled when `MASTODON_ACCESS_TOKEN` is set.
+    pub(crate) fn from_env() -> Result<Option<Self>> {
+        let access_token = match optional_env_var("MASTODON_ACCESS_TOKEN") {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        let max_length = match optional_env_var("MASTODON_MAX_LENGTH") {
+            Some(x) => x.parse().context("failed to decode MASTODON_MAX_LENGTH")?,
+            None => DEFAULT_MAX_LENGTH,
+        };
+
+        Ok(Some(Self::new(
+            env_var("MASTODON_INSTANCE_URL"),
+            access_token,
+            max_length,
+        )))
+    }
+}
+
+#[async_trait]
+impl SocialPoster for MastodonClient {
+    fn name(&self) -> &'static str {
+        "mastodon"
+    }
+
+    fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    async fn post(&self, text: &str) -> Result<String> {
+        let response = self
+            .http
+            .post(format!("{}/api/v1/statuses", self.instance_url))
+            .bearer_auth(&self.access_token)
+            .json(&json!({ "status": text }))
+            .send()
+            .await
+            .context("failed to send request")?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("mastodon returned {}: {}", status, body);
+        }
+
+        let posted = response
+            .json::<Status>()
+            .await
+            .context("failed to decode response")?;
+
+        Ok(posted.url)
+    }
+}
+
+#[tokio::test]
+async fn test_post() {
+    let (base, server) = crate::test_util::mock_http_server(vec![(
+        200,
+        r#"{"id":"1","url":"https://example.com/@lt/1"}"#,
+    )])
+    .await;
+
+    let client = MastodonClient::new(base, "token".into(), DEFAULT_MAX_LENGTH);
+
+    let url = client.post("hello").await.unwrap();
+    assert_eq!(url, "https://example.com/@lt/1");
+
+    let request = server.await.unwrap()[0].to_lowercase();
+    assert!(request.starts_with("post /api/v1/statuses "));
+    assert!(request.contains("authorization: bearer token"));
+    assert!(request.ends_with(r#"{"status":"hello"}"#));
+}
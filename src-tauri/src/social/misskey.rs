@@ -0,0 +1,113 @@
+use {
+    super::SocialPoster,
+    crate::{env_var, optional_env_var},
+    anyhow::{Context as _, Result},
+    async_trait::async_trait,
+    serde::Deserialize,
+    serde_json::json,
+};
+
+// default of vanilla Misskey. some instances change it.
+const DEFAULT_MAX_LENGTH: usize = 3000;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateNoteResponse {
+    created_note: Note,
+}
+
+#[derive(Deserialize)]
+struct Note {
+    id: String,
+}
+
+/// posts notes through `POST /api/notes/create`.
+pub(crate) struct MisskeyClient {
+    http: reqwest::Client,
+    instance_url: String,
+    access_token: String,
+    max_length: usize,
+}
+
+impl MisskeyClient {
+    pub(crate) fn new(instance_url: String, access_token: String, max_length: usize) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            instance_url: instance_url.trim_end_matches('/').to_string(),
+            access_token,
+            max_length,
+        }
+    }
+
+    /// enabled when `MISSKEY_ACCESS_TOKEN` is set.
+    pub(crate) fn from_env() -> Result<Option<Self>> {
+        let access_token = match optional_env_var("MISSKEY_ACCESS_TOKEN") {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        let max_length = match optional_env_var("MISSKEY_MAX_LENGTH") {
+            Some(x) => x.parse().context("failed to decode MISSKEY_MAX_LENGTH")?,
+            None => DEFAULT_MAX_LENGTH,
+        };
+
+        Ok(Some(Self::new(
+            env_var("MISSKEY_INSTANCE_URL"),
+            access_token,
+            max_length,
+        )))
+    }
+}
+
+#[async_trait]
+impl SocialPoster for MisskeyClient {
+    fn name(&self) -> &'static str {
+        "misskey"
+    }
+
+    fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    async fn post(&self, text: &str) -> Result<String> {
+        let response = self
+            .http
+            .post(format!("{}/api/notes/create", self.instance_url))
+            .json(&json!({ "i": self.access_token, "text": text }))
+            .send()
+            .await
+            .context("failed to send request")?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("misskey returned {}: {}", status, body);
+        }
+
+        let created = response
+            .json::<CreateNoteResponse>()
+            .await
+            .context("failed to decode response")?;
+
+        Ok(format!(
+            "{}/notes/{}",
+            self.instance_url, created.created_note.id
+        ))
+    }
+}
+
+#[tokio::test]
+async fn test_post() {
+    let (base, server) =
+        crate::test_util::mock_http_server(vec![(200, r#"{"createdNote":{"id":"9abc"}}"#)]).await;
+
+    let client = MisskeyClient::new(base.clone(), "token".into(), DEFAULT_MAX_LENGTH);
+
+    let url = client.post("hello").await.unwrap();
+    assert_eq!(url, format!("{}/notes/9abc", base));
+
+    let request = server.await.unwrap()[0].to_lowercase();
+    assert!(request.starts_with("post /api/notes/create "));
+    assert!(request.ends_with(r#"{"i":"token","text":"hello"}"#));
+}
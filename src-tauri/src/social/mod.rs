@@ -0,0 +1,94 @@
+//! backends to post messages to social services.
+
+#[cfg(feature = "bluesky")]
+pub(crate) mod bluesky;
+
+#[cfg(feature = "mastodon")]
+pub(crate) mod mastodon;
+
+#[cfg(feature = "misskey")]
+pub(crate) mod misskey;
+
+#[cfg(feature = "twitter")]
+pub(crate) mod twitter;
+
+use {anyhow::Result, async_trait::async_trait, futures::future::join_all};
+
+/// a service which `Tweet` and `PresentationTweet` commands post to.
+#[async_trait]
+pub(crate) trait SocialPoster: Send + Sync {
+    /// shown in replies to operators
+    fn name(&self) -> &'static str;
+
+    fn max_length(&self) -> usize;
+
+    /// length of `text` counted in the way of the service
+    fn length(&self, text: &str) -> usize {
+        text.chars().count()
+    }
+
+    /// returns the URL of the post
+    async fn post(&self, text: &str) -> Result<String>;
+}
+
+/// creates posters enabled in config.
+/// every service is enabled when its credential is set and the feature is compiled in.
+pub(crate) fn posters_from_env() -> Result<Vec<Box<dyn SocialPoster>>> {
+    #[allow(unused_mut)]
+    let mut posters = Vec::<Box<dyn SocialPoster>>::new();
+
+    #[cfg(feature = "twitter")]
+    if let Some(client) = twitter::TwitterClient::from_env() {
+        posters.push(Box::new(client));
+    }
+
+    #[cfg(feature = "bluesky")]
+    if let Some(client) = bluesky::BlueskyClient::from_env() {
+        posters.push(Box::new(client));
+    }
+
+    #[cfg(feature = "mastodon")]
+    if let Some(client) = mastodon::MastodonClient::from_env()? {
+        posters.push(Box::new(client));
+    }
+
+    #[cfg(feature = "misskey")]
+    if let Some(client) = misskey::MisskeyClient::from_env()? {
+        posters.push(Box::new(client));
+    }
+
+    for poster in &posters {
+        tracing::info!("posting to {} is enabled", poster.name());
+    }
+
+    Ok(posters)
+}
+
+/// returns a description of every violated length rule.
+pub(crate) fn check_length(posters: &[Box<dyn SocialPoster>], text: &str) -> Vec<String> {
+    posters
+        .iter()
+        .filter(|x| x.length(text) > x.max_length())
+        .map(|x| {
+            format!(
+                "{}: {} exceeds the limit {}",
+                x.name(),
+                x.length(text),
+                x.max_length()
+            )
+        })
+        .collect()
+}
+
+/// posts `text` to every service at once.
+pub(crate) async fn post_all(
+    posters: &[Box<dyn SocialPoster>],
+    text: &str,
+) -> Vec<(&'static str, Result<String>)> {
+    join_all(
+        posters
+            .iter()
+            .map(|x| async move { (x.name(), x.post(text).await) }),
+    )
+    .await
+}
@@ -0,0 +1,259 @@
+use {
+    super::SocialPoster,
+    crate::{env_var, optional_env_var},
+    anyhow::{Context as _, Result},
+    async_trait::async_trait,
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine as _},
+    hmac::{Hmac, Mac},
+    percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC},
+    rand::{distributions::Alphanumeric, Rng},
+    serde::Deserialize,
+    serde_json::json,
+    sha1::Sha1,
+    std::time::{SystemTime, UNIX_EPOCH},
+};
+
+pub(crate) const DEFAULT_API_BASE: &str = "https://api.twitter.com";
+
+const MAX_LENGTH: usize = 280;
+
+// characters which must not be encoded in OAuth 1.0a (RFC 3986 unreserved)
+const OAUTH_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+fn encode(s: &str) -> String {
+    utf8_percent_encode(s, OAUTH_ENCODE_SET).to_string()
+}
+
+/// OAuth 1.0a user context credentials of the account to post from.
+pub(crate) struct TwitterCredentials {
+    pub(crate) consumer_key: String,
+    pub(crate) consumer_secret: String,
+    pub(crate) access_token: String,
+    pub(crate) access_token_secret: String,
+}
+
+#[derive(Deserialize)]
+struct CreateTweetResponse {
+    data: CreatedTweet,
+}
+
+#[derive(Deserialize)]
+struct CreatedTweet {
+    id: String,
+}
+
+/// posts tweets through v2 `POST /2/tweets`.
+pub(crate) struct TwitterClient {
+    http: reqwest::Client,
+    api_base: String,
+    credentials: TwitterCredentials,
+}
+
+impl TwitterClient {
+    /// `api_base` is replaceable to talk to a mock server.
+    pub(crate) fn new(credentials: TwitterCredentials, api_base: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            api_base: api_base.trim_end_matches('/').to_string(),
+            credentials,
+        }
+    }
+
+    /// enabled when `TWITTER_CONSUMER_KEY` is set.
+    pub(crate) fn from_env() -> Option<Self> {
+        let credentials = TwitterCredentials {
+            consumer_key: optional_env_var("TWITTER_CONSUMER_KEY")?,
+            consumer_secret: env_var("TWITTER_CONSUMER_SECRET"),
+            access_token: env_var("TWITTER_ACCESS_TOKEN"),
+            access_token_secret: env_var("TWITTER_ACCESS_TOKEN_SECRET"),
+        };
+
+        let api_base =
+            optional_env_var("TWITTER_API_BASE").unwrap_or_else(|| DEFAULT_API_BASE.to_string());
+
+        Some(Self::new(credentials, api_base))
+    }
+
+    fn authorization(&self, method: &str, url: &str) -> String {
+        let nonce = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect::<String>();
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            .to_string();
+
+        // JSON bodies are not part of the signature, so only oauth_* params are signed.
+        let mut params = vec![
+            ("oauth_consumer_key", self.credentials.consumer_key.as_str()),
+            ("oauth_nonce", nonce.as_str()),
+            ("oauth_signature_method", "HMAC-SHA1"),
+            ("oauth_timestamp", timestamp.as_str()),
+            ("oauth_token", self.credentials.access_token.as_str()),
+            ("oauth_version", "1.0"),
+        ];
+
+        let signature = signature(
+            method,
+            url,
+            &params,
+            &self.credentials.consumer_secret,
+            &self.credentials.access_token_secret,
+        );
+
+        params.push(("oauth_signature", &signature));
+
+        let header = params
+            .iter()
+            .map(|(k, v)| format!(r#"{}="{}""#, encode(k), encode(v)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        format!("OAuth {}", header)
+    }
+}
+
+#[async_trait]
+impl SocialPoster for TwitterClient {
+    fn name(&self) -> &'static str {
+        "twitter"
+    }
+
+    fn max_length(&self) -> usize {
+        MAX_LENGTH
+    }
+
+    // CJK characters are counted as 2
+    fn length(&self, text: &str) -> usize {
+        text.chars().map(|x| if x.is_ascii() { 1 } else { 2 }).sum()
+    }
+
+    async fn post(&self, text: &str) -> Result<String> {
+        let url = format!("{}/2/tweets", self.api_base);
+
+        let response = self
+            .http
+            .post(&url)
+            .header("Authorization", self.authorization("POST", &url))
+            .json(&json!({ "text": text }))
+            .send()
+            .await
+            .context("failed to send request")?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("twitter returned {}: {}", status, body);
+        }
+
+        let tweet = response
+            .json::<CreateTweetResponse>()
+            .await
+            .context("failed to decode response")?;
+
+        Ok(format!(
+            "https://twitter.com/i/web/status/{}",
+            tweet.data.id
+        ))
+    }
+}
+
+/// HMAC-SHA1 signature defined in RFC 5849 section 3.4.
+fn signature(
+    method: &str,
+    url: &str,
+    params: &[(&str, &str)],
+    consumer_secret: &str,
+    token_secret: &str,
+) -> String {
+    let mut encoded = params
+        .iter()
+        .map(|(k, v)| (encode(k), encode(v)))
+        .collect::<Vec<_>>();
+
+    encoded.sort();
+
+    let param_string = encoded
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let base = format!(
+        "{}&{}&{}",
+        method.to_uppercase(),
+        encode(url),
+        encode(&param_string)
+    );
+
+    let key = format!("{}&{}", encode(consumer_secret), encode(token_secret));
+
+    let mut mac = Hmac::<Sha1>::new_from_slice(key.as_bytes()).expect("HMAC accepts any key");
+    mac.update(base.as_bytes());
+
+    BASE64.encode(mac.finalize().into_bytes())
+}
+
+#[test]
+fn test_signature() {
+    // example in https://developer.twitter.com/en/docs/authentication/oauth-1-0a/creating-a-signature
+    let signature = signature(
+        "post",
+        "https://api.twitter.com/1.1/statuses/update.json",
+        &[
+            (
+                "status",
+                "Hello Ladies + Gentlemen, a signed OAuth request!",
+            ),
+            ("include_entities", "true"),
+            ("oauth_consumer_key", "xvz1evFS4wEEPTGEFPHBog"),
+            ("oauth_nonce", "kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg"),
+            ("oauth_signature_method", "HMAC-SHA1"),
+            ("oauth_timestamp", "1318622958"),
+            (
+                "oauth_token",
+                "370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb",
+            ),
+            ("oauth_version", "1.0"),
+        ],
+        "kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw",
+        "LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE",
+    );
+
+    assert_eq!(signature, "hCtSmYh+iHYCEqBWrE7C7hYmtUk=");
+}
+
+#[tokio::test]
+async fn test_post() {
+    let (base, server) =
+        crate::test_util::mock_http_server(vec![(201, r#"{"data":{"id":"1234","text":"hello"}}"#)])
+            .await;
+
+    let client = TwitterClient::new(
+        TwitterCredentials {
+            consumer_key: "key".into(),
+            consumer_secret: "secret".into(),
+            access_token: "token".into(),
+            access_token_secret: "token secret".into(),
+        },
+        base,
+    );
+
+    let url = client.post("hello").await.unwrap();
+    assert_eq!(url, "https://twitter.com/i/web/status/1234");
+
+    let request = server.await.unwrap()[0].to_lowercase();
+    assert!(request.starts_with("post /2/tweets "));
+    assert!(request.contains("authorization: oauth oauth_consumer_key=\"key\""));
+    assert!(request.contains("oauth_signature="));
+    assert!(request.ends_with(r#"{"text":"hello"}"#));
+}
@@ -0,0 +1,285 @@
+use {
+    crate::{
+        model::{
+            presentation_json, timeline_card_json, timer_json, Content, Highlight, Page,
+            ScreenAction, Service, User,
+        },
+        Context,
+    },
+    serde_json::json,
+    std::collections::VecDeque,
+};
+
+// views show only the latest few cards. older ones are not worth replaying.
+const TIMELINE_HISTORY_LIMIT: usize = 100;
+// texts listed for operators are cut at this many characters
+const LISTED_TEXT_LIMIT: usize = 40;
+
+struct TimelineEntry {
+    id: u64,
+    author: String,
+    source_id: Option<String>,
+    user: User,
+    service: Service,
+    content: Content,
+    highlight: Option<Highlight>,
+}
+
+struct TimerEntry {
+    seconds: u64,
+    paused: bool,
+    overtime: bool,
+}
+
+/// what the screen currently shows. every `ScreenAction` sent to views is applied to this,
+/// so that a view connecting (or reloading) later can catch up with a snapshot.
+pub(crate) struct ScreenState {
+    timeline: VecDeque<TimelineEntry>,
+    notification: Option<String>,
+    presentation: Option<(User, String)>,
+    page: Page,
+    timer: Option<TimerEntry>,
+}
+
+impl ScreenState {
+    pub(crate) fn new() -> Self {
+        Self {
+            timeline: VecDeque::new(),
+            notification: None,
+            presentation: None,
+            page: Page::WaitingScreen,
+            timer: None,
+        }
+    }
+
+    pub(crate) fn notification(&self) -> Option<&str> {
+        self.notification.as_deref()
+    }
+
+    pub(crate) fn page(&self) -> Page {
+        self.page
+    }
+
+    /// ID of the item shown for the message `source_id` of `service`, if it's still shown.
+    pub(crate) fn find_by_source(&self, service: Service, source_id: &str) -> Option<u64> {
+        self.timeline
+            .iter()
+            .find(|x| x.service == service && x.source_id.as_deref() == Some(source_id))
+            .map(|x| x.id)
+    }
+
+    /// the latest items as "<ID> <author>: <text>", for operators to choose one to remove.
+    pub(crate) fn recent_items(&self, limit: usize) -> Vec<String> {
+        let skip = self.timeline.len().saturating_sub(limit);
+
+        self.timeline
+            .iter()
+            .skip(skip)
+            .map(|x| {
+                let text = x.content.plain_text().replace('\n', " ");
+                let mut chars = text.chars();
+                let mut short = chars.by_ref().take(LISTED_TEXT_LIMIT).collect::<String>();
+
+                if chars.next().is_some() {
+                    short.push('…');
+                }
+
+                format!("{} {}: {}", x.id, x.author, short)
+            })
+            .collect()
+    }
+
+    pub(crate) fn apply(&mut self, action: &ScreenAction) {
+        use ScreenAction::*;
+
+        match action {
+            TimelineClear => self.timeline.clear(),
+
+            TimelinePush {
+                id,
+                author,
+                source_id,
+                user,
+                service,
+                content,
+                highlight,
+            } => {
+                if self.timeline.len() >= TIMELINE_HISTORY_LIMIT {
+                    self.timeline.pop_front();
+                }
+
+                self.timeline.push_back(TimelineEntry {
+                    id: *id,
+                    author: author.clone(),
+                    source_id: source_id.clone(),
+                    user: user.clone(),
+                    service: *service,
+                    content: content.clone(),
+                    highlight: highlight.clone(),
+                });
+            }
+
+            TimelineUpdate { id, content } => {
+                if let Some(entry) = self.timeline.iter_mut().find(|x| x.id == *id) {
+                    entry.content = content.clone();
+                }
+            }
+
+            TimelineRemove { id } => self.timeline.retain(|x| x.id != *id),
+
+            TimelineRemoveUser { author } => self.timeline.retain(|x| x.author != *author),
+
+            NotificationUpdate { text } => self.notification = Some(text.clone()),
+
+            PresentationUpdate { presenter, title } => {
+                self.presentation = Some((presenter.clone(), title.clone()))
+            }
+
+            PresentationClear => self.presentation = None,
+
+            SwitchPage(page) => self.page = *page,
+
+            // upcoming presentations are read from `Context` when taking a snapshot
+            UpcomingPresentationsUpdate(_) => {}
+
+            TimerUpdate {
+                remaining_secs,
+                paused,
+            } => {
+                self.timer = Some(TimerEntry {
+                    seconds: *remaining_secs,
+                    paused: *paused,
+                    overtime: false,
+                })
+            }
+
+            TimerOvertime {
+                overtime_secs,
+                paused,
+            } => {
+                self.timer = Some(TimerEntry {
+                    seconds: *overtime_secs,
+                    paused: *paused,
+                    overtime: true,
+                })
+            }
+
+            TimerClear => self.timer = None,
+        }
+    }
+
+    /// serializes the whole state as a `screen.snapshot` event.
+    pub(crate) async fn snapshot(&self, ctx: &Context) -> String {
+        let json = json!({
+            "type": "screen.snapshot",
+            "args": {
+                "new": {
+                    "presentation": self
+                        .presentation
+                        .as_ref()
+                        .map(|(presenter, title)| presentation_json(presenter, title)),
+                    "pending_presentation": ctx.presentations.read().await.to_json_value(),
+                    "timeline": self
+                        .timeline
+                        .iter()
+                        .map(|x| {
+                            timeline_card_json(
+                                x.id,
+                                &x.author,
+                                &x.user,
+                                x.service,
+                                &x.content,
+                                x.highlight.as_ref(),
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                    "notification": self.notification,
+                    "timer": self
+                        .timer
+                        .as_ref()
+                        .map(|x| timer_json(x.seconds, x.paused, x.overtime)),
+                    "page": self.page,
+                }
+            }
+        });
+
+        serde_json::to_string(&json).unwrap()
+    }
+}
+
+#[test]
+fn test_snapshot() {
+    let ctx = Context::for_test(None);
+    let mut state = ScreenState::new();
+
+    let user = crate::test_util::user("name");
+
+    for i in 0..TIMELINE_HISTORY_LIMIT + 1 {
+        state.apply(&ScreenAction::TimelinePush {
+            id: i as u64,
+            author: format!("discord:{}", i % 2),
+            source_id: Some(i.to_string()),
+            user: user.clone(),
+            service: Service::Discord,
+            content: Content::from_text(&i.to_string()),
+            highlight: None,
+        });
+    }
+
+    state.apply(&ScreenAction::NotificationUpdate {
+        text: "notification".into(),
+    });
+    state.apply(&ScreenAction::SwitchPage(Page::LTScreen));
+    state.apply(&ScreenAction::TimerOvertime {
+        overtime_secs: 3,
+        paused: false,
+    });
+
+    let snapshot = ctx.rt.block_on(state.snapshot(&ctx));
+    let json = serde_json::from_str::<serde_json::Value>(&snapshot).unwrap();
+    let new = &json["args"]["new"];
+
+    assert_eq!(json["type"], "screen.snapshot");
+    assert_eq!(new["presentation"], serde_json::Value::Null);
+    assert_eq!(new["notification"], "notification");
+    assert_eq!(new["page"], "LTScreen");
+    assert_eq!(new["timer"]["overtime"], true);
+
+    let timeline = new["timeline"].as_array().unwrap();
+    assert_eq!(timeline.len(), TIMELINE_HISTORY_LIMIT);
+    assert_eq!(timeline[0]["content"][0]["text"], "1");
+    assert_eq!(timeline[0]["id"], 1);
+
+    assert_eq!(
+        state.recent_items(2),
+        ["99 discord:1: 99", "100 discord:0: 100"]
+    );
+
+    assert_eq!(state.find_by_source(Service::Discord, "6"), Some(6));
+    assert_eq!(state.find_by_source(Service::Youtube, "6"), None);
+
+    state.apply(&ScreenAction::TimelineUpdate {
+        id: 1,
+        content: Content::from_text("edited"),
+    });
+    assert_eq!(state.timeline[0].content.plain_text(), "edited");
+
+    state.apply(&ScreenAction::TimelineRemove { id: 6 });
+    assert_eq!(state.find_by_source(Service::Discord, "6"), None);
+    assert_eq!(state.timeline.len(), TIMELINE_HISTORY_LIMIT - 1);
+
+    // odd IDs are by "discord:1"
+    state.apply(&ScreenAction::TimelineRemoveUser {
+        author: "discord:1".into(),
+    });
+    assert_eq!(state.timeline.len(), TIMELINE_HISTORY_LIMIT / 2 - 1);
+
+    state.apply(&ScreenAction::TimelineClear);
+    state.apply(&ScreenAction::TimerClear);
+
+    let snapshot = ctx.rt.block_on(state.snapshot(&ctx));
+    let json = serde_json::from_str::<serde_json::Value>(&snapshot).unwrap();
+
+    assert_eq!(json["args"]["new"]["timeline"], serde_json::json!([]));
+    assert_eq!(json["args"]["new"]["timer"], serde_json::Value::Null);
+}
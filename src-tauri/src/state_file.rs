@@ -0,0 +1,175 @@
+use {
+    crate::{model::Page, presentations::Presentation, Context},
+    anyhow::{Context as _, Result},
+    serde::{Deserialize, Serialize},
+    std::{
+        path::{Path, PathBuf},
+        sync::Arc,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+    tokio::{fs, io::AsyncWriteExt, sync::Notify},
+};
+
+/// everything needed to continue the event after restarting the app.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SavedState {
+    pub(crate) presentations: Vec<Presentation>,
+    pub(crate) current_presentation: Option<Presentation>,
+    pub(crate) finished_presentations: Vec<Presentation>,
+    pub(crate) listening_channel_id: Option<u64>,
+    pub(crate) notification: Option<String>,
+    pub(crate) page: Page,
+}
+
+impl SavedState {
+    async fn collect(ctx: &Context) -> Self {
+        let (notification, page) = {
+            let state = ctx.screen_state.read().await;
+            (state.notification().map(str::to_owned), state.page())
+        };
+
+        Self {
+            presentations: ctx.presentations.read().await.to_vec(),
+            current_presentation: ctx.current_presentation.read().await.clone(),
+            finished_presentations: ctx.finished_presentations.read().await.clone(),
+            listening_channel_id: *ctx.listening_channel_id.read().await,
+            notification,
+            page,
+        }
+    }
+}
+
+/// a single JSON file holding `SavedState`.
+/// it's replaced atomically so that a crash while writing never leaves a broken file.
+pub(crate) struct StateFile {
+    path: PathBuf,
+    dirty: Notify,
+}
+
+impl StateFile {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            dirty: Notify::new(),
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// moves the file of the previous run aside, so that starting over without `--resume`
+    /// by mistake doesn't lose it. None if there's nothing to keep.
+    pub(crate) async fn back_up(&self) -> Result<Option<PathBuf>> {
+        if !fs::try_exists(&self.path).await.unwrap_or(false) {
+            return Ok(None);
+        }
+
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let mut backup = self.path.clone().into_os_string();
+        backup.push(format!(".{}.bak", secs));
+        let backup = PathBuf::from(backup);
+
+        fs::rename(&self.path, &backup)
+            .await
+            .with_context(|| format!("failed to move {} aside", self.path.display()))?;
+
+        Ok(Some(backup))
+    }
+
+    /// schedules writing the current state. multiple requests in a row are coalesced.
+    pub(crate) fn request_save(&self) {
+        self.dirty.notify_one();
+    }
+
+    pub(crate) async fn load(&self) -> Result<SavedState> {
+        let json = fs::read_to_string(&self.path)
+            .await
+            .context("failed to read file")?;
+
+        serde_json::from_str(&json).context("failed to deserialize saved state")
+    }
+
+    async fn write(&self, state: &SavedState) -> Result<()> {
+        let json = serde_json::to_vec_pretty(state).context("failed to serialize state")?;
+
+        write_atomically(&self.path, &json).await
+    }
+}
+
+/// writes to a temporary file first so that a crash never leaves a half-written file.
+pub(crate) async fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut temp_path = path.to_path_buf().into_os_string();
+    temp_path.push(".tmp");
+
+    let mut file = fs::File::create(&temp_path)
+        .await
+        .context("failed to create temporary file")?;
+
+    file.write_all(contents)
+        .await
+        .context("failed to write temporary file")?;
+
+    file.sync_all()
+        .await
+        .context("failed to flush temporary file")?;
+
+    fs::rename(&temp_path, path)
+        .await
+        .with_context(|| format!("failed to replace {}", path.display()))
+}
+
+/// writes the state whenever it's requested through `StateFile::request_save`.
+pub(crate) async fn run_saver(ctx: Arc<Context>) {
+    loop {
+        ctx.state_file.dirty.notified().await;
+
+        let state = SavedState::collect(&ctx).await;
+
+        if let Err(e) = ctx.state_file.write(&state).await {
+            tracing::error!(
+                "failed to save state to {}: {:?}",
+                ctx.state_file.path.display(),
+                e
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_state_file() {
+    let presentation = |title: &str| crate::test_util::presentation("name", title, None);
+
+    let path = std::env::temp_dir().join(format!("sugoi-state-{}.json", std::process::id()));
+    let file = StateFile::new(path.clone());
+
+    assert!(file.load().await.is_err());
+    assert!(file.back_up().await.unwrap().is_none());
+
+    file.write(&SavedState {
+        presentations: vec![presentation("next")],
+        current_presentation: Some(presentation("current")),
+        finished_presentations: vec![presentation("finished")],
+        listening_channel_id: Some(42),
+        notification: Some("notification".into()),
+        page: Page::LTScreen,
+    })
+    .await
+    .unwrap();
+
+    let loaded = file.load().await.unwrap();
+    assert_eq!(loaded.presentations[0].title, "next");
+    assert_eq!(loaded.current_presentation.unwrap().title, "current");
+    assert_eq!(loaded.finished_presentations[0].title, "finished");
+    assert_eq!(loaded.listening_channel_id, Some(42));
+    assert_eq!(loaded.notification.as_deref(), Some("notification"));
+    assert!(matches!(loaded.page, Page::LTScreen));
+
+    let backup = file.back_up().await.unwrap().unwrap();
+    assert!(file.load().await.is_err());
+    std::fs::remove_file(backup).unwrap();
+}
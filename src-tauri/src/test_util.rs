@@ -0,0 +1,99 @@
+//! helpers shared by tests
+
+use crate::{
+    model::{Content, Service, User},
+    moderation::Incoming,
+    presentations::Presentation,
+};
+
+pub(crate) fn user(name: &str) -> User {
+    User {
+        icon: None,
+        ident: None,
+        name: name.into(),
+    }
+}
+
+pub(crate) fn presentation(
+    presenter: &str,
+    title: &str,
+    duration_secs: Option<u64>,
+) -> Presentation {
+    Presentation {
+        presenter: user(presenter),
+        title: title.into(),
+        duration_secs,
+    }
+}
+
+/// a plain message from `author`, who is also the display name
+pub(crate) fn incoming(service: Service, author: &str, text: &str) -> Incoming {
+    Incoming {
+        service,
+        author: author.into(),
+        source_id: None,
+        user: user(author),
+        content: Content::from_text(text),
+        highlight: None,
+    }
+}
+
+/// HTTP server which replies `responses` in order, one per connection.
+/// resolves to the raw requests it received.
+pub(crate) async fn mock_http_server(
+    responses: Vec<(u16, &'static str)>,
+) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = tokio::spawn(async move {
+        let mut requests = vec![];
+
+        for (status, body) in responses {
+            let (mut socket, _) = listener.accept().await.unwrap();
+
+            let mut request = vec![];
+            let mut buf = [0; 4096];
+
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                assert_ne!(n, 0, "connection closed before the request was complete");
+                request.extend_from_slice(&buf[..n]);
+
+                let text = String::from_utf8_lossy(&request).to_lowercase();
+
+                let Some(header_end) = text.find("\r\n\r\n") else {
+                    continue;
+                };
+
+                let content_length = text[..header_end]
+                    .lines()
+                    .find_map(|x| x.strip_prefix("content-length: "))
+                    .map_or(0, |x| x.trim().parse::<usize>().unwrap());
+
+                if request.len() >= header_end + 4 + content_length {
+                    break;
+                }
+            }
+
+            let response = format!(
+                "HTTP/1.1 {} MOCK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+
+            socket.write_all(response.as_bytes()).await.unwrap();
+            requests.push(String::from_utf8(request).unwrap());
+        }
+
+        requests
+    });
+
+    (base, handle)
+}
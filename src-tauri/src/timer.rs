@@ -0,0 +1,164 @@
+use {
+    crate::{model::ScreenAction, Context},
+    std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::time::interval,
+};
+
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// countdown timer of the ongoing presentation.
+pub(crate) struct PresentationTimer {
+    // the duration of the talk, which `reset` goes back to
+    original: Duration,
+    // extended from `original`
+    allotted: Duration,
+
+    // elapsed time before the last pause. time since `running_since` is added on top of it.
+    elapsed: Duration,
+    running_since: Option<Instant>,
+}
+
+impl PresentationTimer {
+    pub(crate) fn start(allotted: Duration, now: Instant) -> Self {
+        Self {
+            original: allotted,
+            allotted,
+            elapsed: Duration::ZERO,
+            running_since: Some(now),
+        }
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.running_since.is_none()
+    }
+
+    fn elapsed(&self, now: Instant) -> Duration {
+        match self.running_since {
+            Some(since) => self.elapsed + now.saturating_duration_since(since),
+            None => self.elapsed,
+        }
+    }
+
+    /// returns false if already paused
+    pub(crate) fn pause(&mut self, now: Instant) -> bool {
+        if self.is_paused() {
+            return false;
+        }
+
+        self.elapsed = self.elapsed(now);
+        self.running_since = None;
+
+        true
+    }
+
+    /// returns false if already running
+    pub(crate) fn resume(&mut self, now: Instant) -> bool {
+        if !self.is_paused() {
+            return false;
+        }
+
+        self.running_since = Some(now);
+
+        true
+    }
+
+    pub(crate) fn extend(&mut self, by: Duration) {
+        self.allotted += by;
+    }
+
+    /// restarts the countdown from the duration of the talk without extensions,
+    /// keeping paused state.
+    pub(crate) fn reset(&mut self, now: Instant) {
+        self.allotted = self.original;
+        self.elapsed = Duration::ZERO;
+
+        if !self.is_paused() {
+            self.running_since = Some(now);
+        }
+    }
+
+    pub(crate) fn to_action(&self, now: Instant) -> ScreenAction {
+        let elapsed = self.elapsed(now);
+        let paused = self.is_paused();
+
+        match self.allotted.checked_sub(elapsed) {
+            Some(remaining) => ScreenAction::TimerUpdate {
+                // round up so that "0" is shown only after the time is actually up.
+                remaining_secs: (remaining + Duration::from_nanos(999_999_999)).as_secs(),
+                paused,
+            },
+
+            None => ScreenAction::TimerOvertime {
+                overtime_secs: (elapsed - self.allotted).as_secs(),
+                paused,
+            },
+        }
+    }
+}
+
+/// sends the state of the timer to webview every second while a presentation is ongoing.
+pub(crate) async fn run_ticker(ctx: Arc<Context>) {
+    let mut interval = interval(TICK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let action = match ctx.timer.read().await.as_ref() {
+            Some(timer) => timer.to_action(Instant::now()),
+            None => continue,
+        };
+
+        if let Some(chan) = ctx.webview_chan.read().await.as_ref() {
+            chan.send(action).await.ok();
+        }
+    }
+}
+
+#[test]
+fn test_timer() {
+    fn remaining(timer: &PresentationTimer, now: Instant) -> (i64, bool) {
+        match timer.to_action(now) {
+            ScreenAction::TimerUpdate {
+                remaining_secs,
+                paused,
+            } => (remaining_secs as i64, paused),
+
+            ScreenAction::TimerOvertime {
+                overtime_secs,
+                paused,
+            } => (-(overtime_secs as i64), paused),
+
+            _ => unreachable!(),
+        }
+    }
+
+    let secs = Duration::from_secs;
+    let t0 = Instant::now();
+
+    let mut timer = PresentationTimer::start(secs(300), t0);
+    assert_eq!(remaining(&timer, t0), (300, false));
+    assert_eq!(remaining(&timer, t0 + secs(100)), (200, false));
+
+    assert!(timer.pause(t0 + secs(100)));
+    assert!(!timer.pause(t0 + secs(110)));
+    assert_eq!(remaining(&timer, t0 + secs(200)), (200, true));
+
+    assert!(timer.resume(t0 + secs(200)));
+    assert_eq!(remaining(&timer, t0 + secs(250)), (150, false));
+
+    timer.extend(secs(60));
+    assert_eq!(remaining(&timer, t0 + secs(250)), (210, false));
+    assert_eq!(remaining(&timer, t0 + secs(520)), (-60, false));
+
+    // extensions are undone
+    timer.reset(t0 + secs(520));
+    assert_eq!(remaining(&timer, t0 + secs(520)), (300, false));
+
+    timer.extend(secs(30));
+    assert!(timer.pause(t0 + secs(530)));
+    timer.reset(t0 + secs(540));
+    assert_eq!(remaining(&timer, t0 + secs(600)), (300, true));
+}
@@ -1,9 +1,8 @@
-import { listen } from "@tauri-apps/api/event";
 import { useEffect } from "react";
 
-import type { Action } from "../lib/data/reducer";
 import { useReducerWithMiddleware } from "../lib/data/reducer";
 import type { Page, ScreenData } from "../lib/data/ScreenData";
+import { subscribeScreenEvents } from "../lib/ScreenEvents";
 import styles from "../style/app.module.scss";
 import { LTScreen } from "./ltscreen/LTScreen";
 import { WaitingScreen } from "./waiting/WaitingScreen";
@@ -23,15 +22,7 @@ function App(): JSX.Element {
   const CurrentPage = selectPage(state.transition.current);
   const TransitingPage = state.transition.to && selectPage(state.transition.to);
 
-  useEffect(() => {
-    const unlisten = listen("event", (data) => {
-      dispatch(JSON.parse(data.payload as string) as Action);
-    });
-
-    return () => {
-      void unlisten.then((f) => f());
-    };
-  }, [dispatch]);
+  useEffect(() => subscribeScreenEvents(dispatch), [dispatch]);
 
   return (
     <div className={styles.screen_container}>
@@ -11,6 +11,6 @@ export const LTScreen = ({ state }: LTScreenProps): JSX.Element => (
   <div>
     <Notification notification={state.notification ?? ""} />
     <Main timeline={state.timeline} />
-    <Footer presentation={state.presentation} />
+    <Footer presentation={state.presentation} timer={state.timer} />
   </div>
 );
@@ -1,37 +1,44 @@
-import { Presentation } from "../../../lib/data/ScreenData";
+import { Presentation, Timer as TimerData } from "../../../lib/data/ScreenData";
 import styles from "../../../style/ltscreen/footer.module.scss";
+import { Timer } from "./Timer";
 
 type FooterProps = {
-  presentation: Presentation;
+  presentation?: Presentation | undefined;
+  timer?: TimerData | undefined;
 };
 
-export const Footer = ({ presentation }: FooterProps): JSX.Element => (
+export const Footer = ({ presentation, timer }: FooterProps): JSX.Element => (
   <footer className={styles.footer_root}>
-    <div className={styles.icon}>
-      <img
-        src={presentation.presenter.userIcon}
-        alt=""
-        style={{
-          objectPosition: `0% ${
-            (presentation.icon_fit_position ?? 0.5) * 100
-          }%`,
-        }}
-      />
-    </div>
-    <p className={styles.presenter}>
-      <span className={styles.presenter_name}>
-        {presentation.presenter.name}
-      </span>
-      {presentation.presenter.identifier != null && (
-        <span>
-          (
-          <span className={styles.presenter_ident}>
-            @{presentation.presenter.identifier}
+    {presentation != null && (
+      <>
+        <div className={styles.icon}>
+          <img
+            src={presentation.presenter.userIcon}
+            alt=""
+            style={{
+              objectPosition: `0% ${
+                (presentation.icon_fit_position ?? 0.5) * 100
+              }%`,
+            }}
+          />
+        </div>
+        <p className={styles.presenter}>
+          <span className={styles.presenter_name}>
+            {presentation.presenter.name}
           </span>
-          )
-        </span>
-      )}
-    </p>
-    <p className={styles.title}>{presentation.title}</p>
+          {presentation.presenter.identifier != null && (
+            <span>
+              (
+              <span className={styles.presenter_ident}>
+                @{presentation.presenter.identifier}
+              </span>
+              )
+            </span>
+          )}
+        </p>
+        <p className={styles.title}>{presentation.title}</p>
+      </>
+    )}
+    {timer != null && <Timer timer={timer} />}
   </footer>
 );
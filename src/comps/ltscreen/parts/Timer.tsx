@@ -0,0 +1,29 @@
+import { Timer as TimerData } from "../../../lib/data/ScreenData";
+import styles from "../../../style/ltscreen/timer.module.scss";
+
+type TimerProps = {
+  timer: TimerData;
+};
+
+function format(seconds: number): string {
+  const min = Math.floor(seconds / 60);
+  const sec = seconds % 60;
+  return `${min}:${sec.toString().padStart(2, "0")}`;
+}
+
+export const Timer = ({ timer }: TimerProps): JSX.Element => {
+  const classNames = [styles.timer_root];
+  if (timer.overtime) {
+    classNames.push(styles.overtime);
+  }
+  if (timer.paused) {
+    classNames.push(styles.paused);
+  }
+
+  return (
+    <div className={classNames.join(" ")}>
+      {timer.overtime && "+"}
+      {format(timer.seconds)}
+    </div>
+  );
+};
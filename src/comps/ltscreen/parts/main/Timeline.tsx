@@ -1,4 +1,9 @@
-import { Service, TimelineCard } from "../../../../lib/data/ScreenData";
+import {
+  Highlight,
+  Segment,
+  Service,
+  TimelineCard,
+} from "../../../../lib/data/ScreenData";
 import { useWindowDimensions } from "../../../../lib/WindowHooks";
 import styles from "../../../../style/ltscreen/main.module.scss";
 
@@ -8,6 +13,54 @@ const icon: { [key in Service]: string } = {
   youtube: "/font-awesome/youtube-brands.svg",
 };
 
+// Membership items have no tier, so they use YouTube's member green
+const MEMBERSHIP_COLOR = "#0f9d58";
+
+const highlightColor = (highlight: Highlight): string =>
+  highlight.kind === "membership" ? MEMBERSHIP_COLOR : highlight.color;
+
+const HighlightHeader = ({
+  highlight,
+}: {
+  highlight: Highlight;
+}): JSX.Element => (
+  <div
+    className={styles.card_highlight_header}
+    style={{ backgroundColor: highlightColor(highlight) }}
+  >
+    {highlight.kind === "membership" ? highlight.header : highlight.amount}
+    {highlight.kind === "super_sticker" && highlight.sticker != null && (
+      <img
+        src={highlight.sticker}
+        alt=""
+        className={styles.card_highlight_sticker}
+      />
+    )}
+  </div>
+);
+
+const SegmentView = ({ segment }: { segment: Segment }): JSX.Element => {
+  switch (segment.type) {
+    case "text":
+      return <>{segment.text}</>;
+    case "emoji":
+      return (
+        <img
+          src={segment.url}
+          alt={segment.alt}
+          title={segment.alt}
+          className={styles.card_emoji}
+        />
+      );
+    case "mention":
+      return <span className={styles.card_mention}>{segment.name}</span>;
+    case "link":
+      return <span className={styles.card_link}>{segment.text}</span>;
+    case "code":
+      return <code className={styles.card_code}>{segment.text}</code>;
+  }
+};
+
 type TimelineProps = {
   timeline: Array<TimelineCard>;
 };
@@ -22,9 +75,28 @@ export const Timeline = ({ timeline }: TimelineProps): JSX.Element => {
       className={styles.timeline}
       style={{ height: `${netWidth * (9 / 16)}px` }}
     >
-      {timeline.map((card, index) => (
-        <div className={styles.card} key={index}>
-          <p className={styles.card_content}>{card.content}</p>
+      {timeline.map((card) => (
+        <div
+          className={
+            card.highlight != null ? styles.card_highlight : styles.card
+          }
+          style={
+            card.highlight != null
+              ? { borderColor: highlightColor(card.highlight) }
+              : undefined
+          }
+          key={card.id}
+        >
+          {card.highlight != null && (
+            <HighlightHeader highlight={card.highlight} />
+          )}
+          {card.content.length > 0 && (
+            <p className={styles.card_content}>
+              {card.content.map((segment, i) => (
+                <SegmentView segment={segment} key={i} />
+              ))}
+            </p>
+          )}
           <div className={styles.card_detail}>
             <span>
               {card.user.userIcon != null && (
@@ -6,6 +6,11 @@ import { createRoot } from "react-dom/client";
 
 import App from "./comps/App";
 
+// `?transparent` lets OBS composite the slides behind the mask instead of chroma keying.
+if (new URLSearchParams(window.location.search).has("transparent")) {
+  document.documentElement.dataset["transparent"] = "";
+}
+
 const container = document.getElementById("root");
 if (container == null) throw new Error("No root element");
 const root = createRoot(container);
@@ -0,0 +1,56 @@
+import { emit, listen } from "@tauri-apps/api/event";
+
+import type { Action } from "./data/reducer";
+
+const RECONNECT_INTERVAL = 1000;
+
+// tauri injects its IPC handler into the window. otherwise we are loaded as a browser source.
+const isTauri = (): boolean => "__TAURI_IPC__" in window;
+
+const parse = (payload: unknown): Action =>
+  JSON.parse(payload as string) as Action;
+
+const subscribeTauri = (handler: (action: Action) => void): (() => void) => {
+  const unlisten = listen("event", (data) => {
+    handler(parse(data.payload));
+  });
+
+  // Ask the backend for a snapshot once we can receive it
+  void unlisten.then(() => emit("screen.ready"));
+
+  return () => {
+    void unlisten.then((f) => f());
+  };
+};
+
+const subscribeWebSocket = (
+  handler: (action: Action) => void,
+): (() => void) => {
+  let socket: WebSocket | undefined;
+  let closed = false;
+
+  const connect = () => {
+    // The backend sends a snapshot first on every connection
+    socket = new WebSocket(`ws://${window.location.host}/events`);
+    socket.addEventListener("message", (event) => {
+      handler(parse(event.data));
+    });
+    socket.addEventListener("close", () => {
+      if (!closed) {
+        setTimeout(connect, RECONNECT_INTERVAL);
+      }
+    });
+  };
+
+  connect();
+
+  return () => {
+    closed = true;
+    socket?.close();
+  };
+};
+
+export const subscribeScreenEvents = (
+  handler: (action: Action) => void,
+): (() => void) =>
+  isTauri() ? subscribeTauri(handler) : subscribeWebSocket(handler);
@@ -12,21 +12,69 @@ export type Presentation = {
 
 export type Service = "twitter" | "discord" | "youtube";
 
+export type Highlight =
+  | {
+      kind: "super_chat";
+      amount: string;
+      color: string;
+      tier: number | null;
+    }
+  | {
+      kind: "super_sticker";
+      amount: string;
+      color: string;
+      tier: number | null;
+      sticker: string | null;
+    }
+  | {
+      kind: "membership";
+      header: string;
+    };
+
+export type Segment =
+  | { type: "text"; text: string }
+  | { type: "emoji"; url: string; alt: string }
+  | { type: "mention"; name: string }
+  | { type: "link"; url: string; text: string }
+  | { type: "code"; text: string };
+
 export type TimelineCard = {
+  id: number;
+  // "<service>:<author>", shared by every card of the same author
+  author: string;
   user: Person;
   service: Service;
-  content: string;
+  content: Array<Segment>;
+  highlight?: Highlight | null;
+};
+
+export type Timer = {
+  seconds: number;
+  paused: boolean;
+  overtime: boolean;
 };
 
 export type Page = "LTScreen" | "WaitingScreen";
 
 export type ScreenData = {
-  presentation: Presentation;
+  // undefined until a presentation is popped, or after the first pop is undone
+  presentation?: Presentation;
   pending_presentation: Array<Presentation>;
   timeline: Array<TimelineCard>;
   notification?: string;
+  timer?: Timer;
   transition: {
     current: Page;
     to?: Page;
   };
 };
+
+// Whole state sent by the backend when a view connects
+export type Snapshot = {
+  presentation: Presentation | null;
+  pending_presentation: Array<Presentation>;
+  timeline: Array<TimelineCard>;
+  notification: string | null;
+  timer: Timer | null;
+  page: Page;
+};
@@ -1,6 +1,14 @@
 import { Dispatch, useReducer } from "react";
 
-import { Page, Presentation, ScreenData, TimelineCard } from "./ScreenData";
+import {
+  Page,
+  Presentation,
+  ScreenData,
+  Segment,
+  Snapshot,
+  TimelineCard,
+  Timer,
+} from "./ScreenData";
 
 export type Action =
   | {
@@ -15,6 +23,10 @@ export type Action =
         new: Presentation;
       };
     }
+  | {
+      type: "presentation.clear";
+      args: never;
+    }
   | {
       type: "timeline.add";
       args: {
@@ -25,6 +37,25 @@ export type Action =
       type: "timeline.flush";
       args: never;
     }
+  | {
+      type: "timeline.update";
+      args: {
+        id: number;
+        content: Array<Segment>;
+      };
+    }
+  | {
+      type: "timeline.remove";
+      args: {
+        id: number;
+      };
+    }
+  | {
+      type: "timeline.remove_user";
+      args: {
+        author: string;
+      };
+    }
   | {
       type: "waiting.message.update";
       args: {
@@ -37,6 +68,22 @@ export type Action =
         new: Array<Presentation>;
       };
     }
+  | {
+      type: "timer.update";
+      args: {
+        new: Timer;
+      };
+    }
+  | {
+      type: "timer.clear";
+      args: never;
+    }
+  | {
+      type: "screen.snapshot";
+      args: {
+        new: Snapshot;
+      };
+    }
   | {
       type: "screen.update";
       args: {
@@ -89,15 +136,19 @@ export const initialState: ScreenData = {
   ],
   timeline: [
     {
+      id: 0,
+      author: "youtube:sample0",
       service: "youtube",
       user: {
         userIcon: undefined,
         identifier: "extremely_long_name_of_user_who_did_comment_to_this_event",
         name: "本イベントに関わる発言を行ったユーザが使用するユーザ名",
       },
-      content: "Some random content here",
+      content: [{ type: "text", text: "Some random content here" }],
     },
     {
+      id: 1,
+      author: "discord:sample1",
       service: "discord",
       user: {
         userIcon:
@@ -105,9 +156,11 @@ export const initialState: ScreenData = {
         identifier: "[ident2]",
         name: "Name2",
       },
-      content: "Some random content here",
+      content: [{ type: "text", text: "Some random content here" }],
     },
     {
+      id: 2,
+      author: "twitter:sample2",
       service: "twitter",
       user: {
         userIcon:
@@ -115,7 +168,7 @@ export const initialState: ScreenData = {
         identifier: "[ident3]",
         name: "Name3",
       },
-      content: "Some random content here",
+      content: [{ type: "text", text: "Some random content here" }],
     },
   ],
   notification: "開始までしばらくおまちください",
@@ -170,16 +223,62 @@ function reducer(state: ScreenData, action: Action): ScreenData {
         ...state,
         timeline: [],
       };
+    case "timeline.update":
+      return {
+        ...state,
+        timeline: state.timeline.map((x) =>
+          x.id === action.args.id ? { ...x, content: action.args.content } : x,
+        ),
+      };
+    case "timeline.remove":
+      return {
+        ...state,
+        timeline: state.timeline.filter((x) => x.id !== action.args.id),
+      };
+    case "timeline.remove_user":
+      return {
+        ...state,
+        timeline: state.timeline.filter(
+          (x) => x.author !== action.args.author,
+        ),
+      };
     case "presentation.update":
       return {
         ...state,
         presentation: action.args.new,
       };
+    case "presentation.clear":
+      return {
+        ...state,
+        presentation: undefined,
+      };
     case "waiting.pending.update":
       return {
         ...state,
         pending_presentation: action.args.new,
       };
+    case "timer.update":
+      return {
+        ...state,
+        timer: action.args.new,
+      };
+    case "timer.clear":
+      return {
+        ...state,
+        timer: undefined,
+      };
+    case "screen.snapshot":
+      return {
+        ...state,
+        presentation: action.args.new.presentation ?? undefined,
+        pending_presentation: action.args.new.pending_presentation,
+        timeline: action.args.new.timeline,
+        notification: action.args.new.notification ?? undefined,
+        timer: action.args.new.timer ?? undefined,
+        transition: {
+          current: action.args.new.page,
+        },
+      };
     case "screen.update":
       return state;
     case "screen.startTransition":